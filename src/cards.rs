@@ -0,0 +1,386 @@
+use rand::prelude::*;
+use std::collections::VecDeque;
+
+use crate::edition::{edition, Decks};
+use crate::{BoardGroup, Road};
+
+// What a card does to the player who draws it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CardAction {
+    AdvanceTo(Road),
+    GoBackTo(Road),
+    GoBack(u8),
+    // on to the next station or utility, where an owner is owed more than usual
+    AdvanceToNearest(BoardGroup),
+    GoToJail,
+    Collect(u128),
+    Pay(u128),
+    CollectFromEachPlayer(u128),
+    PayEachPlayer(u128),
+    Repairs { house: u128, hotel: u128 },
+    GetOutOfJailFree,
+}
+
+impl CardAction {
+    // Where the token ends up after this card, or None if it stays put.
+    pub fn destination(&self, from: Road) -> Option<Road> {
+        match self {
+            CardAction::AdvanceTo(r) | CardAction::GoBackTo(r) => Some(*r),
+            CardAction::GoBack(n) => Some(from.back(*n)),
+            CardAction::AdvanceToNearest(group) => Some(from.nearest(*group)),
+            CardAction::GoToJail => Some(Road::Jail),
+            _ => None,
+        }
+    }
+
+    // Net cash for the drawer, given their buildings and how many other players are in the game.
+    pub fn cash(&self, houses: u128, hotels: u128, others: u128) -> i128 {
+        match self {
+            CardAction::Collect(n) => *n as i128,
+            CardAction::Pay(n) => -(*n as i128),
+            CardAction::CollectFromEachPlayer(n) => (n * others) as i128,
+            CardAction::PayEachPlayer(n) => -((n * others) as i128),
+            CardAction::Repairs { house, hotel } => -((house * houses + hotel * hotels) as i128),
+            _ => 0,
+        }
+    }
+}
+
+// A card drawn by the player, recorded whether or not it moved them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CardEvent {
+    pub square: Road,
+    pub action: CardAction,
+}
+
+// The UK edition Chance deck.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChanceCard {
+    AdvanceToGo,
+    AdvanceToTrafalgarSquare,
+    AdvanceToPallMall,
+    AdvanceToMayfair,
+    TripToMaryleboneStation,
+    GoBackThreeSpaces,
+    GoToJail,
+    BankDividend,
+    SchoolFees,
+    SpeedingFine,
+    BuildingLoanMatures,
+    CrosswordCompetition,
+    GeneralRepairs,
+    StreetRepairs,
+    DrunkInCharge,
+    GetOutOfJailFree,
+}
+
+impl ChanceCard {
+    pub fn all() -> Vec<ChanceCard> {
+        vec![
+            ChanceCard::AdvanceToGo,
+            ChanceCard::AdvanceToTrafalgarSquare,
+            ChanceCard::AdvanceToPallMall,
+            ChanceCard::AdvanceToMayfair,
+            ChanceCard::TripToMaryleboneStation,
+            ChanceCard::GoBackThreeSpaces,
+            ChanceCard::GoToJail,
+            ChanceCard::BankDividend,
+            ChanceCard::SchoolFees,
+            ChanceCard::SpeedingFine,
+            ChanceCard::BuildingLoanMatures,
+            ChanceCard::CrosswordCompetition,
+            ChanceCard::GeneralRepairs,
+            ChanceCard::StreetRepairs,
+            ChanceCard::DrunkInCharge,
+            ChanceCard::GetOutOfJailFree,
+        ]
+    }
+
+    pub fn action(self) -> CardAction {
+        match self {
+            ChanceCard::AdvanceToGo => CardAction::AdvanceTo(Road::Go),
+            ChanceCard::AdvanceToTrafalgarSquare => CardAction::AdvanceTo(Road::TrafalgarSquare),
+            ChanceCard::AdvanceToPallMall => CardAction::AdvanceTo(Road::PallMall),
+            ChanceCard::AdvanceToMayfair => CardAction::AdvanceTo(Road::Mayfair),
+            ChanceCard::TripToMaryleboneStation => CardAction::AdvanceTo(Road::MaryleboneStation),
+            ChanceCard::GoBackThreeSpaces => CardAction::GoBack(3),
+            ChanceCard::GoToJail => CardAction::GoToJail,
+            ChanceCard::BankDividend => CardAction::Collect(50),
+            ChanceCard::SchoolFees => CardAction::Pay(150),
+            ChanceCard::SpeedingFine => CardAction::Pay(15),
+            ChanceCard::BuildingLoanMatures => CardAction::Collect(150),
+            ChanceCard::CrosswordCompetition => CardAction::Collect(100),
+            ChanceCard::GeneralRepairs => CardAction::Repairs {
+                house: 25,
+                hotel: 100,
+            },
+            ChanceCard::StreetRepairs => CardAction::Repairs {
+                house: 40,
+                hotel: 115,
+            },
+            ChanceCard::DrunkInCharge => CardAction::Pay(20),
+            ChanceCard::GetOutOfJailFree => CardAction::GetOutOfJailFree,
+        }
+    }
+}
+
+// The UK edition Community Chest deck.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CommunityChestCard {
+    AdvanceToGo,
+    GoBackToOldKentRoad,
+    GoToJail,
+    BankError,
+    DoctorsFee,
+    HospitalFees,
+    InsurancePremium,
+    AnnuityMatures,
+    Inheritance,
+    SaleOfStock,
+    PreferenceShares,
+    IncomeTaxRefund,
+    BeautyContest,
+    Birthday,
+    FineOrChance,
+    GetOutOfJailFree,
+}
+
+impl CommunityChestCard {
+    pub fn all() -> Vec<CommunityChestCard> {
+        vec![
+            CommunityChestCard::AdvanceToGo,
+            CommunityChestCard::GoBackToOldKentRoad,
+            CommunityChestCard::GoToJail,
+            CommunityChestCard::BankError,
+            CommunityChestCard::DoctorsFee,
+            CommunityChestCard::HospitalFees,
+            CommunityChestCard::InsurancePremium,
+            CommunityChestCard::AnnuityMatures,
+            CommunityChestCard::Inheritance,
+            CommunityChestCard::SaleOfStock,
+            CommunityChestCard::PreferenceShares,
+            CommunityChestCard::IncomeTaxRefund,
+            CommunityChestCard::BeautyContest,
+            CommunityChestCard::Birthday,
+            CommunityChestCard::FineOrChance,
+            CommunityChestCard::GetOutOfJailFree,
+        ]
+    }
+
+    pub fn action(self) -> CardAction {
+        match self {
+            CommunityChestCard::AdvanceToGo => CardAction::AdvanceTo(Road::Go),
+            CommunityChestCard::GoBackToOldKentRoad => CardAction::GoBackTo(Road::OldKentRoad),
+            CommunityChestCard::GoToJail => CardAction::GoToJail,
+            CommunityChestCard::BankError => CardAction::Collect(200),
+            CommunityChestCard::DoctorsFee => CardAction::Pay(50),
+            CommunityChestCard::HospitalFees => CardAction::Pay(100),
+            CommunityChestCard::InsurancePremium => CardAction::Pay(50),
+            CommunityChestCard::AnnuityMatures => CardAction::Collect(100),
+            CommunityChestCard::Inheritance => CardAction::Collect(100),
+            CommunityChestCard::SaleOfStock => CardAction::Collect(50),
+            CommunityChestCard::PreferenceShares => CardAction::Collect(25),
+            CommunityChestCard::IncomeTaxRefund => CardAction::Collect(20),
+            CommunityChestCard::BeautyContest => CardAction::Collect(10),
+            CommunityChestCard::Birthday => CardAction::CollectFromEachPlayer(10),
+            // we always pay the fine rather than take a Chance
+            CommunityChestCard::FineOrChance => CardAction::Pay(10),
+            CommunityChestCard::GetOutOfJailFree => CardAction::GetOutOfJailFree,
+        }
+    }
+}
+
+// The US edition Chance deck. Squares are named by where they are on the board, so Boardwalk is
+// Road::Mayfair.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UsChanceCard {
+    AdvanceToGo,
+    AdvanceToBoardwalk,
+    AdvanceToIllinoisAvenue,
+    AdvanceToStCharlesPlace,
+    AdvanceToNearestRailroad,
+    AdvanceToNearestRailroadAgain,
+    AdvanceToNearestUtility,
+    TripToReadingRailroad,
+    GoBackThreeSpaces,
+    GoToJail,
+    BankDividend,
+    BuildingLoanMatures,
+    SpeedingFine,
+    GeneralRepairs,
+    ChairmanOfTheBoard,
+    GetOutOfJailFree,
+}
+
+impl UsChanceCard {
+    pub fn all() -> Vec<UsChanceCard> {
+        vec![
+            UsChanceCard::AdvanceToGo,
+            UsChanceCard::AdvanceToBoardwalk,
+            UsChanceCard::AdvanceToIllinoisAvenue,
+            UsChanceCard::AdvanceToStCharlesPlace,
+            UsChanceCard::AdvanceToNearestRailroad,
+            UsChanceCard::AdvanceToNearestRailroadAgain,
+            UsChanceCard::AdvanceToNearestUtility,
+            UsChanceCard::TripToReadingRailroad,
+            UsChanceCard::GoBackThreeSpaces,
+            UsChanceCard::GoToJail,
+            UsChanceCard::BankDividend,
+            UsChanceCard::BuildingLoanMatures,
+            UsChanceCard::SpeedingFine,
+            UsChanceCard::GeneralRepairs,
+            UsChanceCard::ChairmanOfTheBoard,
+            UsChanceCard::GetOutOfJailFree,
+        ]
+    }
+
+    pub fn action(self) -> CardAction {
+        match self {
+            UsChanceCard::AdvanceToGo => CardAction::AdvanceTo(Road::Go),
+            UsChanceCard::AdvanceToBoardwalk => CardAction::AdvanceTo(Road::Mayfair),
+            UsChanceCard::AdvanceToIllinoisAvenue => CardAction::AdvanceTo(Road::TrafalgarSquare),
+            UsChanceCard::AdvanceToStCharlesPlace => CardAction::AdvanceTo(Road::PallMall),
+            UsChanceCard::AdvanceToNearestRailroad
+            | UsChanceCard::AdvanceToNearestRailroadAgain => {
+                CardAction::AdvanceToNearest(BoardGroup::Station)
+            }
+            UsChanceCard::AdvanceToNearestUtility => {
+                CardAction::AdvanceToNearest(BoardGroup::Utility)
+            }
+            UsChanceCard::TripToReadingRailroad => CardAction::AdvanceTo(Road::KingsCrossStation),
+            UsChanceCard::GoBackThreeSpaces => CardAction::GoBack(3),
+            UsChanceCard::GoToJail => CardAction::GoToJail,
+            UsChanceCard::BankDividend => CardAction::Collect(50),
+            UsChanceCard::BuildingLoanMatures => CardAction::Collect(150),
+            UsChanceCard::SpeedingFine => CardAction::Pay(15),
+            UsChanceCard::GeneralRepairs => CardAction::Repairs {
+                house: 25,
+                hotel: 100,
+            },
+            UsChanceCard::ChairmanOfTheBoard => CardAction::PayEachPlayer(50),
+            UsChanceCard::GetOutOfJailFree => CardAction::GetOutOfJailFree,
+        }
+    }
+}
+
+// The US edition Community Chest deck.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UsCommunityChestCard {
+    AdvanceToGo,
+    GoToJail,
+    BankError,
+    DoctorsFee,
+    SaleOfStock,
+    HolidayFund,
+    IncomeTaxRefund,
+    Birthday,
+    LifeInsurance,
+    HospitalFees,
+    SchoolFees,
+    ConsultancyFee,
+    StreetRepairs,
+    BeautyContest,
+    Inheritance,
+    GetOutOfJailFree,
+}
+
+impl UsCommunityChestCard {
+    pub fn all() -> Vec<UsCommunityChestCard> {
+        vec![
+            UsCommunityChestCard::AdvanceToGo,
+            UsCommunityChestCard::GoToJail,
+            UsCommunityChestCard::BankError,
+            UsCommunityChestCard::DoctorsFee,
+            UsCommunityChestCard::SaleOfStock,
+            UsCommunityChestCard::HolidayFund,
+            UsCommunityChestCard::IncomeTaxRefund,
+            UsCommunityChestCard::Birthday,
+            UsCommunityChestCard::LifeInsurance,
+            UsCommunityChestCard::HospitalFees,
+            UsCommunityChestCard::SchoolFees,
+            UsCommunityChestCard::ConsultancyFee,
+            UsCommunityChestCard::StreetRepairs,
+            UsCommunityChestCard::BeautyContest,
+            UsCommunityChestCard::Inheritance,
+            UsCommunityChestCard::GetOutOfJailFree,
+        ]
+    }
+
+    pub fn action(self) -> CardAction {
+        match self {
+            UsCommunityChestCard::AdvanceToGo => CardAction::AdvanceTo(Road::Go),
+            UsCommunityChestCard::GoToJail => CardAction::GoToJail,
+            UsCommunityChestCard::BankError => CardAction::Collect(200),
+            UsCommunityChestCard::DoctorsFee => CardAction::Pay(50),
+            UsCommunityChestCard::SaleOfStock => CardAction::Collect(50),
+            UsCommunityChestCard::HolidayFund => CardAction::Collect(100),
+            UsCommunityChestCard::IncomeTaxRefund => CardAction::Collect(20),
+            UsCommunityChestCard::Birthday => CardAction::CollectFromEachPlayer(10),
+            UsCommunityChestCard::LifeInsurance => CardAction::Collect(100),
+            UsCommunityChestCard::HospitalFees => CardAction::Pay(100),
+            UsCommunityChestCard::SchoolFees => CardAction::Pay(50),
+            UsCommunityChestCard::ConsultancyFee => CardAction::Collect(25),
+            UsCommunityChestCard::StreetRepairs => CardAction::Repairs {
+                house: 40,
+                hotel: 115,
+            },
+            UsCommunityChestCard::BeautyContest => CardAction::Collect(10),
+            UsCommunityChestCard::Inheritance => CardAction::Collect(100),
+            UsCommunityChestCard::GetOutOfJailFree => CardAction::GetOutOfJailFree,
+        }
+    }
+}
+
+// The Chance cards for the board in use.
+pub fn chance() -> Vec<CardAction> {
+    match edition().decks {
+        Decks::Uk => ChanceCard::all().into_iter().map(|c| c.action()).collect(),
+        Decks::Us => UsChanceCard::all()
+            .into_iter()
+            .map(|c| c.action())
+            .collect(),
+    }
+}
+
+// The Community Chest cards for the board in use.
+pub fn community_chest() -> Vec<CardAction> {
+    match edition().decks {
+        Decks::Uk => CommunityChestCard::all()
+            .into_iter()
+            .map(|c| c.action())
+            .collect(),
+        Decks::Us => UsCommunityChestCard::all()
+            .into_iter()
+            .map(|c| c.action())
+            .collect(),
+    }
+}
+
+// A shuffled deck: cards are drawn from the top and returned to the bottom.
+pub struct Deck<C> {
+    cards: VecDeque<C>,
+}
+
+impl<C: Copy> Deck<C> {
+    pub fn new<T: Rng>(mut cards: Vec<C>, rng: &mut T) -> Deck<C> {
+        cards.shuffle(rng);
+        Deck {
+            cards: cards.into_iter().collect(),
+        }
+    }
+
+    pub fn draw(&mut self) -> C {
+        let card = self.take();
+        self.put_back(card);
+        card
+    }
+
+    // Draw a card without returning it, for cards that the player keeps until they're used.
+    pub fn take(&mut self) -> C {
+        self.cards.pop_front().unwrap()
+    }
+
+    pub fn put_back(&mut self, card: C) {
+        self.cards.push_back(card);
+    }
+}
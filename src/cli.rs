@@ -0,0 +1,295 @@
+use rand::prelude::*;
+
+use crate::edition::Edition;
+use crate::game::{self, Auction};
+use crate::strategy;
+use crate::JailStrategy;
+
+pub const USAGE: &str = "\
+Usage: monopoly <command> [options]
+
+Commands:
+  simulate        Simulate games, and report how often each square is landed on
+  solve           Work out the landing frequencies exactly, in the long run and over games of
+                  the given turns, and check the latter against simulated games
+  report          Work out the expected landings over the first turns of a game exactly
+  compare-jail    Simulate games under both jail strategies, and show where they differ
+  play            Play full games between 2 to 8 players, buying property and paying rent,
+                  and report who wins
+  help            Print this message
+
+Options:
+  --games <n>     Number of games to play [default: 100000]
+  --turns <n>     Turns per game, or rounds before a game is called for play [default: 50,
+                  or 1000 for play]
+  --players <n>   Tokens on the board in each game [default: 1, or 4 for play]
+  --jail <rule>   How to get out of jail: short (pay at once) or long (roll for doubles)
+                  [default: long]
+  --strategy <s>  Comma separated strategies for play, dealt out to the seats in turn:
+                  always, no-utilities, oranges-reds, hoard or reserve:<cash>
+                  [default: always]
+  --auction <a>   How squares nobody buys are sold in play: english, sealed or off
+                  [default: english]
+  --edition <e>   Which built-in board to play on: uk, or us (Atlantic City) [default: uk]
+  --board <path>  Play on the board described in a JSON file, rather than a built-in one
+  --format <fmt>  Output format: text, or json or csv for simulate [default: text]
+  --output <path> Write json or csv results to a file rather than standard output
+  --seed <n>      Seed for the random number generator, to reproduce an earlier run
+                  [default: chosen at random, and printed with the results]
+  --batch <n>     Games between progress updates, and in each batch that errors are estimated
+                  from [default: 1000]
+  --threads <n>   Worker threads to play games on [default: one per core]
+  --tolerance <x> Stop once every landing and win percentage is known to within this many
+                  percentage points, at 95% confidence, with --games as the most to play
+  --time <secs>   Stop after this many seconds, if all the games haven't been played by then
+
+Each command only takes the options that mean something to it:
+  simulate        all but --strategy and --auction
+  solve           as simulate, but not --format or --output
+  report          --turns, --jail, --edition and --board
+  compare-jail    as solve, but not --jail
+  play            --games, --turns, --players, --jail, --strategy, --auction, --edition,
+                  --board and --seed
+";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    Simulate,
+    Solve,
+    Report,
+    CompareJail,
+    Play,
+    Help,
+}
+
+pub struct Options {
+    pub command: Command,
+    pub games: u64,
+    pub turns: u64,
+    pub batch: u64,
+    pub tolerance: Option<f64>,
+    pub time: Option<u64>,
+    pub players: usize,
+    pub threads: usize,
+    pub jailstrategy: JailStrategy,
+    pub strategies: Vec<String>,
+    pub auction: Auction,
+    pub edition: Option<String>,
+    pub board: Option<String>,
+    pub format: Format,
+    pub output: Option<String>,
+    pub seed: u64,
+}
+
+impl Options {
+    fn new(command: Command) -> Options {
+        let play = command == Command::Play;
+        Options {
+            command,
+            games: 100000,
+            turns: if play { 1000 } else { 50 },
+            batch: 1000,
+            tolerance: None,
+            time: None,
+            players: if play { 4 } else { 1 },
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            jailstrategy: JailStrategy::LongStay,
+            strategies: vec![String::from("always")],
+            auction: Auction::English,
+            edition: None,
+            board: None,
+            format: Format::Text,
+            output: None,
+            seed: thread_rng().gen(),
+        }
+    }
+}
+
+fn number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got '{}'", flag, value))
+}
+
+fn positive(flag: &str, value: &str) -> Result<u64, String> {
+    match number(flag, value)? {
+        0 => Err(format!("{} must be at least 1", flag)),
+        n => Ok(n),
+    }
+}
+
+fn command_name(command: Command) -> &'static str {
+    match command {
+        Command::Simulate => "simulate",
+        Command::Solve => "solve",
+        Command::Report => "report",
+        Command::CompareJail => "compare-jail",
+        Command::Play => "play",
+        Command::Help => "help",
+    }
+}
+
+// The options each command takes. Anything else would be silently ignored, so is refused.
+fn flags(command: Command) -> &'static [&'static str] {
+    match command {
+        Command::Simulate => &[
+            "--games",
+            "--turns",
+            "--batch",
+            "--players",
+            "--threads",
+            "--jail",
+            "--tolerance",
+            "--time",
+            "--edition",
+            "--board",
+            "--format",
+            "--output",
+            "--seed",
+        ],
+        Command::Solve => &[
+            "--games",
+            "--turns",
+            "--batch",
+            "--players",
+            "--threads",
+            "--jail",
+            "--tolerance",
+            "--time",
+            "--edition",
+            "--board",
+            "--seed",
+        ],
+        Command::Report => &["--turns", "--jail", "--edition", "--board"],
+        // both jail rules are played, so there's no choosing one
+        Command::CompareJail => &[
+            "--games",
+            "--turns",
+            "--batch",
+            "--players",
+            "--threads",
+            "--tolerance",
+            "--time",
+            "--edition",
+            "--board",
+            "--seed",
+        ],
+        Command::Play => &[
+            "--games",
+            "--turns",
+            "--players",
+            "--jail",
+            "--strategy",
+            "--auction",
+            "--edition",
+            "--board",
+            "--seed",
+        ],
+        Command::Help => &[],
+    }
+}
+
+// Parse the command line, not including the program name.
+pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let command = match args.next().as_deref() {
+        Some("simulate") => Command::Simulate,
+        Some("solve") => Command::Solve,
+        Some("report") => Command::Report,
+        Some("compare-jail") => Command::CompareJail,
+        Some("play") => Command::Play,
+        Some("help") | Some("--help") | Some("-h") | None => Command::Help,
+        Some(other) => return Err(format!("unknown command '{}'", other)),
+    };
+    let mut options = Options::new(command);
+    let name = command_name(command);
+
+    while let Some(flag) = args.next() {
+        if flag == "--help" || flag == "-h" {
+            options.command = Command::Help;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} expects a value", flag))?;
+        match flag.as_str() {
+            "--games" => options.games = positive(&flag, &value)?,
+            "--turns" => options.turns = positive(&flag, &value)?,
+            "--batch" => options.batch = positive(&flag, &value)?,
+            "--tolerance" => match number::<f64>(&flag, &value)? {
+                t if t > 0.0 => options.tolerance = Some(t),
+                _ => return Err(format!("{} must be more than 0", flag)),
+            },
+            "--time" => options.time = Some(positive(&flag, &value)?),
+            "--players" => options.players = positive(&flag, &value)? as usize,
+            "--threads" => options.threads = positive(&flag, &value)? as usize,
+            "--seed" => options.seed = number(&flag, &value)?,
+            "--jail" => {
+                options.jailstrategy = match value.as_str() {
+                    "short" => JailStrategy::ShortStay,
+                    "long" => JailStrategy::LongStay,
+                    _ => return Err(format!("unknown jail rule '{}'", value)),
+                }
+            }
+            "--format" => {
+                options.format = match value.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    "csv" => Format::Csv,
+                    _ => return Err(format!("unknown format '{}'", value)),
+                }
+            }
+            "--strategy" => {
+                options.strategies = value.split(',').map(String::from).collect();
+            }
+            "--auction" => {
+                options.auction = match value.as_str() {
+                    "english" => Auction::English,
+                    "sealed" => Auction::Sealed,
+                    "off" => Auction::Off,
+                    _ => return Err(format!("unknown auction '{}'", value)),
+                }
+            }
+            "--edition" => {
+                Edition::builtin(&value)?;
+                options.edition = Some(value);
+            }
+            "--board" => options.board = Some(value),
+            "--output" => options.output = Some(value),
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+        if !flags(command).contains(&flag.as_str()) {
+            return Err(format!("{} isn't used by {}", flag, name));
+        }
+    }
+
+    if options.edition.is_some() && options.board.is_some() {
+        return Err(String::from("--edition and --board can't be used together"));
+    }
+
+    if options.command == Command::Play
+        && !(game::MIN_PLAYERS..=game::MAX_PLAYERS).contains(&options.players)
+    {
+        return Err(format!(
+            "play needs between {} and {} players",
+            game::MIN_PLAYERS,
+            game::MAX_PLAYERS
+        ));
+    }
+
+    for name in options.strategies.iter() {
+        strategy::named(name, options.jailstrategy)?;
+    }
+
+    if options.output.is_some() && options.format == Format::Text {
+        return Err(String::from("--output needs --format json or csv"));
+    }
+
+    Ok(options)
+}
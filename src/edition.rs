@@ -0,0 +1,346 @@
+use num_traits::FromPrimitive;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+use crate::BoardGroup;
+
+// The board everything runs on unless told otherwise.
+const UK: &str = include_str!("../boards/uk.json");
+const US: &str = include_str!("../boards/us.json");
+
+// Which built-in Chance and Community Chest cards a board plays with.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Decks {
+    Uk,
+    Us,
+}
+
+// What sort of square a square is, which decides what happens on landing there.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Go,
+    Street,
+    Station,
+    Utility,
+    Chance,
+    CommunityChest,
+    Tax,
+    Jail,
+    FreeParking,
+    GoToJail,
+}
+
+// The file format: groups of streets, and the forty squares in order from Go.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EditionData {
+    name: String,
+    decks: Decks,
+    groups: Vec<GroupData>,
+    squares: Vec<SquareData>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GroupData {
+    name: String,
+    house: u128,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SquareData {
+    name: String,
+    kind: Kind,
+    group: Option<String>,
+    price: Option<u128>,
+    rent: Option<Vec<u128>>,
+    tax: Option<u128>,
+}
+
+// A square, checked and with its group worked out.
+pub struct Square {
+    pub name: String,
+    pub kind: Kind,
+    pub group: BoardGroup,
+    pub price: Option<u128>,
+    // by houses from none to a hotel for streets, by how many are owned for stations, and the
+    // dice multiplier by how many are owned for utilities
+    pub rent: Vec<u128>,
+    pub tax: u128,
+}
+
+pub struct Edition {
+    pub name: String,
+    pub decks: Decks,
+    pub squares: Vec<Square>,
+    // what a house costs in each group, for the groups that can be built on
+    houses: [Option<u128>; 15],
+}
+
+static EDITION: OnceLock<Edition> = OnceLock::new();
+
+// The board in use, the built-in UK one unless another was chosen at startup.
+pub fn edition() -> &'static Edition {
+    EDITION.get_or_init(|| Edition::parse(UK).unwrap())
+}
+
+// Use this board for the rest of the run. Has to happen before anything looks at the board.
+pub fn select(edition: Edition) {
+    if EDITION.set(edition).is_err() {
+        panic!("the board was chosen after it was first used");
+    }
+}
+
+impl Edition {
+    // One of the boards that come with the program, by name.
+    pub fn builtin(name: &str) -> Result<Edition, String> {
+        match name {
+            "uk" => Edition::parse(UK),
+            "us" => Edition::parse(US),
+            _ => Err(format!("unknown edition '{}'", name)),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Edition, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("couldn't read {}: {}", path, e))?;
+        Edition::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    // Read a board, and check that it's one the game can be played on.
+    pub fn parse(text: &str) -> Result<Edition, String> {
+        let data: EditionData = serde_json::from_str(text).map_err(|e| e.to_string())?;
+
+        let mut houses = [None; 15];
+        for g in data.groups.iter() {
+            let group = (0..8)
+                .map(|i| BoardGroup::from_usize(i).unwrap())
+                .find(|group| group.as_str() == g.name)
+                .ok_or_else(|| format!("'{}' isn't a group that can be built on", g.name))?;
+            if houses[group as usize].is_some() {
+                return Err(format!("group '{}' is listed twice", g.name));
+            }
+            houses[group as usize] = Some(g.house);
+        }
+
+        if data.squares.len() != 40 {
+            return Err(format!(
+                "a board needs 40 squares, not {}",
+                data.squares.len()
+            ));
+        }
+
+        let mut squares: Vec<Square> = Vec::new();
+        for (i, s) in data.squares.into_iter().enumerate() {
+            let what = format!("square {} ({})", i, s.name);
+            if s.name.is_empty() {
+                return Err(format!("square {} has no name", i));
+            }
+            if squares.iter().any(|other| other.name == s.name) {
+                return Err(format!("{} has the same name as another square", what));
+            }
+
+            // the corners have to be where the rules expect them
+            let corner = match i {
+                0 => Some(Kind::Go),
+                10 => Some(Kind::Jail),
+                20 => Some(Kind::FreeParking),
+                30 => Some(Kind::GoToJail),
+                _ => None,
+            };
+            let corners = [Kind::Go, Kind::Jail, Kind::FreeParking, Kind::GoToJail];
+            if corner.is_some() && corner != Some(s.kind)
+                || corner.is_none() && corners.contains(&s.kind)
+            {
+                return Err(format!("{} can't be a {:?}", what, s.kind));
+            }
+
+            let rents = match s.kind {
+                Kind::Street => 6,
+                Kind::Station => 4,
+                Kind::Utility => 2,
+                _ => 0,
+            };
+            let rent = s.rent.unwrap_or_default();
+            if rent.len() != rents {
+                return Err(format!(
+                    "{} needs {} rents, not {}",
+                    what,
+                    rents,
+                    rent.len()
+                ));
+            }
+            if rents > 0 && s.price.is_none() {
+                return Err(format!("{} needs a price", what));
+            }
+            if rents == 0 && s.price.is_some() {
+                return Err(format!("{} can't be bought", what));
+            }
+            match (s.kind == Kind::Tax, s.tax.is_some()) {
+                (true, false) => return Err(format!("{} needs a tax", what)),
+                (false, true) => return Err(format!("{} can't have a tax", what)),
+                _ => {}
+            }
+            match (s.kind == Kind::Street, s.group.is_some()) {
+                (true, false) => return Err(format!("{} needs a group", what)),
+                (false, true) => return Err(format!("{} can't be in a group", what)),
+                _ => {}
+            }
+
+            let group = match s.kind {
+                Kind::Street => {
+                    let name = s.group.unwrap();
+                    match houses
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, h)| h.is_some())
+                        .map(|(g, _)| BoardGroup::from_usize(g).unwrap())
+                        .find(|group| group.as_str() == name)
+                    {
+                        Some(group) => group,
+                        None => return Err(format!("{} is in unknown group '{}'", what, name)),
+                    }
+                }
+                Kind::Station => BoardGroup::Station,
+                Kind::Utility => BoardGroup::Utility,
+                Kind::Chance => BoardGroup::Chance,
+                Kind::CommunityChest => BoardGroup::CommunityChest,
+                Kind::Tax => BoardGroup::Tax,
+                Kind::Jail | Kind::GoToJail => BoardGroup::Jail,
+                Kind::Go | Kind::FreeParking => BoardGroup::GoParking,
+            };
+
+            squares.push(Square {
+                name: s.name,
+                kind: s.kind,
+                group,
+                price: s.price,
+                rent,
+                tax: s.tax.unwrap_or(0),
+            });
+        }
+
+        for (g, house) in houses.iter().enumerate() {
+            if house.is_some() && !squares.iter().any(|s| s.group as usize == g) {
+                let group = BoardGroup::from_usize(g).unwrap();
+                return Err(format!("group '{}' has no streets", group.as_str()));
+            }
+        }
+
+        Ok(Edition {
+            name: data.name,
+            decks: data.decks,
+            squares,
+            houses,
+        })
+    }
+
+    pub fn house_cost(&self, group: BoardGroup) -> Option<u128> {
+        self.houses[group as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The UK board with one piece of it swapped for another, which has to be there to swap.
+    fn uk_with(from: &str, to: &str) -> Result<Edition, String> {
+        assert!(UK.contains(from));
+        Edition::parse(&UK.replacen(from, to, 1))
+    }
+
+    fn refused(from: &str, to: &str, error: &str) {
+        match uk_with(from, to) {
+            Ok(_) => panic!("a board with '{}' was accepted", to),
+            Err(e) => assert!(e.contains(error), "'{}' doesn't say '{}'", e, error),
+        }
+    }
+
+    #[test]
+    fn the_builtin_boards_are_fine() {
+        assert_eq!(Edition::builtin("uk").unwrap().decks, Decks::Uk);
+        assert_eq!(Edition::builtin("us").unwrap().decks, Decks::Us);
+        assert!(Edition::builtin("fr").is_err());
+    }
+
+    #[test]
+    fn groups_have_to_be_known_listed_once_and_used() {
+        refused(r#""name": "Brown""#, r#""name": "Beige""#, "isn't a group");
+        refused(
+            r#""name": "LightBlue""#,
+            r#""name": "Brown""#,
+            "listed twice",
+        );
+        refused(
+            r#"{"name": "DarkBlue", "house": 200}"#,
+            r#"{"name": "DarkBlue", "house": 200}, {"name": "Station", "house": 1}"#,
+            "isn't a group",
+        );
+        refused(
+            r#""group": "Brown""#,
+            r#""group": "Beige""#,
+            "unknown group",
+        );
+    }
+
+    #[test]
+    fn a_board_needs_forty_squares_with_the_corners_in_place() {
+        refused(r#"{"name": "Go", "kind": "go"},"#, "", "needs 40 squares");
+        refused(
+            r#"{"name": "Jail", "kind": "jail"}"#,
+            r#"{"name": "Jail", "kind": "free-parking"}"#,
+            "can't be a FreeParking",
+        );
+        refused(
+            r#"{"name": "Chance1", "kind": "chance"}"#,
+            r#"{"name": "Chance1", "kind": "go-to-jail"}"#,
+            "can't be a GoToJail",
+        );
+    }
+
+    #[test]
+    fn squares_need_unique_names() {
+        refused(r#""name": "Chance1""#, r#""name": """#, "has no name");
+        refused(r#""name": "Chance1""#, r#""name": "Go""#, "same name");
+    }
+
+    #[test]
+    fn squares_have_what_their_kind_needs_and_nothing_else() {
+        refused(
+            r#""rent": [2, 10, 30, 90, 160, 250]"#,
+            r#""rent": [2, 10, 30, 90, 160]"#,
+            "needs 6 rents, not 5",
+        );
+        refused(r#""price": 60, "#, "", "needs a price");
+        refused(
+            r#""kind": "chance"}"#,
+            r#""kind": "chance", "price": 1}"#,
+            "can't be bought",
+        );
+        refused(r#", "tax": 200"#, "", "needs a tax");
+        refused(
+            r#""kind": "chance"}"#,
+            r#""kind": "chance", "tax": 1}"#,
+            "can't have a tax",
+        );
+        refused(r#""group": "Brown", "#, "", "needs a group");
+        refused(
+            r#""kind": "chance"}"#,
+            r#""kind": "chance", "group": "Brown"}"#,
+            "can't be in a group",
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_refused() {
+        refused(
+            r#""kind": "go"}"#,
+            r#""kind": "go", "bonus": 400}"#,
+            "bonus",
+        );
+    }
+}
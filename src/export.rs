@@ -0,0 +1,192 @@
+use num_traits::FromPrimitive;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+
+use crate::cli::Options;
+use crate::edition::edition;
+use crate::markov::IN_JAIL;
+use crate::{games_won, landing_name, BoardGroup, Road, Tally};
+
+// One line of results, for either a single square or a whole board group.
+#[derive(Serialize)]
+struct Row {
+    #[serde(skip)]
+    kind: &'static str,
+    name: &'static str,
+    visits: u128,
+    percent: f64,
+    // standard errors of the landing and win percentages, from the spread between batches
+    percent_se: f64,
+    income: u128,
+    // cash from cards drawn there, collected and paid
+    card_income: u128,
+    card_expense: u128,
+    profit: f64,
+    profit_sd: f64,
+    // games won, with ties sharing the credit
+    winners: f64,
+    win_percent_se: f64,
+}
+
+fn rows(tally: &Tally) -> Vec<Row> {
+    let pct = |visits: u128| 100.0 * visits as f64 / tally.turns as f64;
+    let mut rows = Vec::new();
+
+    for (r, visits) in tally.visits.0.iter().enumerate() {
+        rows.push(Row {
+            kind: "road",
+            name: landing_name(r),
+            visits: *visits,
+            percent: pct(*visits),
+            percent_se: tally.visitbatches[r].se(),
+            income: tally.income.0[r],
+            card_income: tally.cardincome.0[r],
+            card_expense: tally.cardexpense.0[r],
+            profit: tally.profit[r].mean(),
+            profit_sd: tally.profit[r].sd(),
+            winners: games_won(tally.winner[r]),
+            win_percent_se: tally.winbatches[r].se(),
+        });
+        if r == Road::Jail as usize {
+            rows.push(Row {
+                kind: "road",
+                name: landing_name(IN_JAIL),
+                visits: tally.injail,
+                percent: pct(tally.injail),
+                percent_se: tally.visitbatches[IN_JAIL].se(),
+                income: 0,
+                card_income: 0,
+                card_expense: 0,
+                profit: 0.0,
+                profit_sd: 0.0,
+                winners: 0.0,
+                win_percent_se: 0.0,
+            });
+        }
+    }
+
+    for (g, visits) in tally.groups.iter().enumerate() {
+        let members = (0..40)
+            .filter(|r| BoardGroup::classify_road(Road::from_usize(*r).unwrap()) as usize == g);
+        let (card_income, card_expense) = members.fold((0, 0), |(i, e), r| {
+            (i + tally.cardincome.0[r], e + tally.cardexpense.0[r])
+        });
+        rows.push(Row {
+            kind: "group",
+            name: BoardGroup::from_usize(g).unwrap().as_str(),
+            visits: *visits,
+            percent: pct(*visits),
+            percent_se: tally.groupbatches[g].se(),
+            income: tally.groupincome[g],
+            card_income,
+            card_expense,
+            profit: tally.groupprofit[g].mean(),
+            profit_sd: tally.groupprofit[g].sd(),
+            winners: games_won(tally.groupwinner[g]),
+            win_percent_se: tally.groupwinbatches[g].se(),
+        });
+    }
+
+    rows
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Value {
+    Number(u128),
+    Text(&'static str),
+}
+
+// The settings that produced a set of results.
+fn metadata(tally: &Tally, options: &Options) -> Vec<(&'static str, Value)> {
+    vec![
+        ("board", Value::Text(&edition().name)),
+        ("seed", Value::Number(options.seed as u128)),
+        (
+            "games",
+            Value::Number((tally.games / options.players as u64) as u128),
+        ),
+        ("turns_per_game", Value::Number(options.turns as u128)),
+        ("players", Value::Number(options.players as u128)),
+        ("batch", Value::Number(options.batch as u128)),
+        ("jail", Value::Text(options.jailstrategy.as_str())),
+        ("turns", Value::Number(tally.turns as u128)),
+    ]
+}
+
+// An error isn't known with fewer than two batches, which leaves its field empty.
+fn number(x: f64) -> String {
+    if x.is_finite() {
+        x.to_string()
+    } else {
+        String::new()
+    }
+}
+
+// The metadata, then the roads and the groups, as a single JSON object. Errors that aren't
+// known yet come out as null.
+struct Document {
+    metadata: Vec<(&'static str, Value)>,
+    rows: Vec<Row>,
+}
+
+impl Serialize for Document {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (key, value) in self.metadata.iter() {
+            map.serialize_entry(key, value)?;
+        }
+        for kind in ["road", "group"] {
+            let rows: Vec<&Row> = self.rows.iter().filter(|row| row.kind == kind).collect();
+            map.serialize_entry(&format!("{}s", kind), &rows)?;
+        }
+        map.end()
+    }
+}
+
+pub fn json(tally: &Tally, options: &Options) -> String {
+    let document = Document {
+        metadata: metadata(tally, options),
+        rows: rows(tally),
+    };
+    serde_json::to_string_pretty(&document).unwrap() + "\n"
+}
+
+// Quote a text field if it has anything in it that a CSV reader would otherwise split on.
+fn text(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+// Run metadata goes in comment lines at the top, which most CSV readers can be told to skip.
+pub fn csv(tally: &Tally, options: &Options) -> String {
+    let mut out = String::new();
+    for (key, value) in metadata(tally, options) {
+        match value {
+            Value::Number(n) => out += &format!("# {}={}\n", key, n),
+            Value::Text(t) => out += &format!("# {}={}\n", key, text(t)),
+        }
+    }
+    out += "kind,name,visits,percent,percent_se,income,card_income,card_expense,profit,profit_sd,winners,win_percent_se\n";
+    for row in rows(tally) {
+        out += &format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            row.kind,
+            text(row.name),
+            row.visits,
+            row.percent,
+            number(row.percent_se),
+            row.income,
+            row.card_income,
+            row.card_expense,
+            row.profit,
+            row.profit_sd,
+            row.winners,
+            number(row.win_percent_se)
+        );
+    }
+    out
+}
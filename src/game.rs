@@ -0,0 +1,1016 @@
+use num_traits::FromPrimitive;
+use rand::prelude::*;
+
+use crate::cards::{self, CardAction, Deck};
+use crate::strategy::{Strategy, View};
+use crate::{BoardGroup, DieRoll, Holding, Rent, Road, RollPair};
+
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 8;
+
+const STARTING_CASH: i64 = 1500;
+const SALARY: i64 = 200;
+const JAIL_FINE: i64 = 50;
+const MIN_BID: i64 = 1;
+// a square's development runs from no houses, through four, to a hotel
+const HOTEL: u8 = 5;
+// all the bank has to sell
+const HOUSES: u8 = 32;
+const HOTELS: u8 = 12;
+
+// How a square nobody buys is sold off.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Auction {
+    // bids go up a pound at a time until only one bidder is left
+    English,
+    // everyone writes down one bid, and the highest pays what they wrote
+    Sealed,
+    // house rule: the square just stays with the bank
+    Off,
+}
+
+impl Auction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Auction::English => "English auctions",
+            Auction::Sealed => "sealed bid auctions",
+            Auction::Off => "no auctions",
+        }
+    }
+}
+
+// Which deck a held "get out of jail free" card has to go back to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Pile {
+    Chance,
+    CommunityChest,
+}
+
+// Who a payment is owed to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Creditor {
+    Bank,
+    Player(usize),
+}
+
+struct Seat {
+    cash: i64,
+    position: Road,
+    // the number of failed attempts to roll out of jail, if we're in it
+    jail: Option<u8>,
+    strategy: Box<dyn Strategy + Send>,
+    jailfree: Vec<Pile>,
+    bankrupt: bool,
+}
+
+// Who went bankrupt, where they were stood at the time, and who they couldn't pay.
+pub struct Bankruptcy {
+    pub player: usize,
+    pub square: Road,
+    pub creditor: Creditor,
+}
+
+// How a game turned out.
+pub struct GameResult {
+    // the last player standing, or the richest when we ran out of rounds
+    pub winner: usize,
+    pub rounds: u64,
+    pub bankruptcies: Vec<Bankruptcy>,
+    // rent collected on each square over the game
+    pub rent: [u128; 40],
+    // every square sold at auction, and what it went for
+    pub auctions: Vec<(Road, i64)>,
+    // how many times each player wanted to build, but the bank had nothing left to sell them
+    pub shortages: Vec<u64>,
+    // houses auctioned because more players wanted them than the bank had left
+    pub house_auctions: u64,
+}
+
+// A full game: players with cash buy the squares they land on and pay rent to each other, until
+// all but one are bankrupt.
+pub struct Game<R: Rng> {
+    seats: Vec<Seat>,
+    owner: [Option<usize>; 40],
+    houses: [u8; 40],
+    mortgaged: [bool; 40],
+    bank_houses: u8,
+    bank_hotels: u8,
+    rent: [u128; 40],
+    bankruptcies: Vec<Bankruptcy>,
+    auction: Auction,
+    auctions: Vec<(Road, i64)>,
+    shortages: Vec<u64>,
+    house_auctions: u64,
+    rng: R,
+    chance: Deck<CardAction>,
+    community_chest: Deck<CardAction>,
+}
+
+impl<R: Rng> Game<R> {
+    pub fn new(strategies: Vec<Box<dyn Strategy + Send>>, auction: Auction, mut rng: R) -> Game<R> {
+        assert!((MIN_PLAYERS..=MAX_PLAYERS).contains(&strategies.len()));
+        let chance = Deck::new(cards::chance(), &mut rng);
+        let community_chest = Deck::new(cards::community_chest(), &mut rng);
+        let seats = strategies.len();
+        Game {
+            seats: strategies
+                .into_iter()
+                .map(|strategy| Seat {
+                    cash: STARTING_CASH,
+                    position: Road::Go,
+                    jail: None,
+                    strategy,
+                    jailfree: Vec::new(),
+                    bankrupt: false,
+                })
+                .collect(),
+            owner: [None; 40],
+            houses: [0; 40],
+            mortgaged: [false; 40],
+            bank_houses: HOUSES,
+            bank_hotels: HOTELS,
+            rent: [0; 40],
+            bankruptcies: Vec::new(),
+            auction,
+            auctions: Vec::new(),
+            shortages: vec![0; seats],
+            house_auctions: 0,
+            rng,
+            chance,
+            community_chest,
+        }
+    }
+
+    // Play until one player is left, or for at most `rounds` times round the table.
+    pub fn play(mut self, rounds: u64) -> GameResult {
+        let mut played = 0;
+        while played < rounds && self.active() > 1 {
+            for p in 0..self.seats.len() {
+                if !self.seats[p].bankrupt {
+                    self.take_turn(p);
+                }
+                if !self.seats[p].bankrupt {
+                    self.develop(p);
+                }
+            }
+            played += 1;
+        }
+
+        GameResult {
+            winner: self.leader(),
+            rounds: played,
+            bankruptcies: self.bankruptcies,
+            rent: self.rent,
+            auctions: self.auctions,
+            shortages: self.shortages,
+            house_auctions: self.house_auctions,
+        }
+    }
+
+    fn view(&self, p: usize) -> View<'_> {
+        View {
+            cash: self.seats[p].cash,
+            houses: &self.houses,
+        }
+    }
+
+    fn active(&self) -> usize {
+        self.seats.iter().filter(|s| !s.bankrupt).count()
+    }
+
+    // Cash plus the price of everything owned, less what's owed on mortgages, and of the
+    // buildings on it. Ties go to the earlier seat.
+    fn leader(&self) -> usize {
+        let mut best = 0;
+        let mut bestworth = i64::MIN;
+        for (p, seat) in self.seats.iter().enumerate() {
+            if seat.bankrupt {
+                continue;
+            }
+            let worth = seat.cash
+                + (0..40)
+                    .filter(|r| self.owner[*r] == Some(p))
+                    .map(|r| {
+                        let road = Road::from_usize(r).unwrap();
+                        let house = BoardGroup::classify_road(road).house_cost().unwrap_or(0);
+                        let mut worth = road.price().unwrap() + self.houses[r] as u128 * house;
+                        if self.mortgaged[r] {
+                            worth -= mortgage_value(road) as u128;
+                        }
+                        worth as i64
+                    })
+                    .sum::<i64>();
+            if worth > bestworth {
+                best = p;
+                bestworth = worth;
+            }
+        }
+        best
+    }
+
+    // Does the player own every square in this group?
+    fn monopoly(&self, p: usize, group: BoardGroup) -> bool {
+        group
+            .members()
+            .iter()
+            .all(|r| self.owner[*r as usize] == Some(p))
+    }
+
+    // Where the player could put a house next, and what it would cost. Only in a group they own
+    // all of with nothing mortgaged, and only on the least developed squares of that group, so
+    // that the houses go up evenly.
+    fn buildable(&self, p: usize) -> Vec<(Road, i64)> {
+        let mut buildable = Vec::new();
+        for group in (0..15).map(|g| BoardGroup::from_usize(g).unwrap()) {
+            let cost = match group.house_cost() {
+                Some(cost)
+                    if self.monopoly(p, group)
+                        && group.members().iter().all(|r| !self.mortgaged[*r as usize]) =>
+                {
+                    cost as i64
+                }
+                _ => continue,
+            };
+            let members = group.members();
+            let lowest = members
+                .iter()
+                .map(|r| self.houses[*r as usize])
+                .min()
+                .unwrap();
+            for road in members {
+                if self.houses[road as usize] == lowest && lowest < HOTEL {
+                    buildable.push((road, cost));
+                }
+            }
+        }
+        buildable
+    }
+
+    // Between turns, let the player pay off mortgages, and build as much as they'd like to and
+    // the bank has stock for.
+    fn develop(&mut self, p: usize) {
+        self.redeem(p);
+        loop {
+            let buildable = self.buildable(p);
+            if buildable.is_empty() {
+                return;
+            }
+            let choice = self.seats[p].strategy.build(&self.view(p), &buildable);
+            let (road, cost) = match buildable.iter().find(|(r, _)| Some(*r) == choice) {
+                Some(b) => *b,
+                None => return,
+            };
+
+            let hotel = self.houses[road as usize] == HOTEL - 1;
+            if (hotel && self.bank_hotels == 0) || (!hotel && self.bank_houses == 0) {
+                self.shortages[p] += 1;
+                return;
+            }
+
+            // mortgage squares in other groups to pay for it, if that's what it takes
+            if !self.raise(p, cost, Some(BoardGroup::classify_road(road))) {
+                return;
+            }
+
+            // when more players want a house than the bank has left, they're auctioned off, from
+            // the house price up
+            if !hotel && self.auction != Auction::Off {
+                let wanted = self.house_wanted(p, road);
+                if wanted.len() > self.bank_houses as usize {
+                    self.house_auctions += 1;
+                    let bids = wanted
+                        .iter()
+                        .map(|(q, r)| {
+                            let bid = self.seats[*q].strategy.house_bid(
+                                &self.view(*q),
+                                *r,
+                                cost,
+                                self.auction,
+                            );
+                            (*q, bid.min(self.seats[*q].cash))
+                        })
+                        .collect();
+                    match settle(self.auction, bids, cost) {
+                        Some((winner, price)) => {
+                            let (_, r) = wanted.iter().find(|(q, _)| *q == winner).unwrap();
+                            self.build(winner, *r, price);
+                            continue;
+                        }
+                        None => return,
+                    }
+                }
+            }
+
+            self.build(p, road, cost);
+        }
+    }
+
+    // Everyone who'd put up a house right now, and where, going round the table from the player
+    // who's building.
+    fn house_wanted(&self, p: usize, road: Road) -> Vec<(usize, Road)> {
+        let seats = self.seats.len();
+        let mut wanted = vec![(p, road)];
+        for q in (1..seats).map(|i| (p + i) % seats) {
+            if self.seats[q].bankrupt {
+                continue;
+            }
+            let buildable: Vec<(Road, i64)> = self
+                .buildable(q)
+                .into_iter()
+                .filter(|(r, cost)| {
+                    self.houses[*r as usize] < HOTEL - 1 && *cost <= self.seats[q].cash
+                })
+                .collect();
+            if buildable.is_empty() {
+                continue;
+            }
+            let choice = self.seats[q].strategy.build(&self.view(q), &buildable);
+            if let Some((r, _)) = buildable.iter().find(|(r, _)| Some(*r) == choice) {
+                wanted.push((q, *r));
+            }
+        }
+        wanted
+    }
+
+    // Put up the next building on a square. A hotel goes up in exchange for the four houses.
+    fn build(&mut self, p: usize, road: Road, price: i64) {
+        if self.houses[road as usize] == HOTEL - 1 {
+            self.bank_houses += HOTEL - 1;
+            self.bank_hotels -= 1;
+        } else {
+            self.bank_houses -= 1;
+        }
+        self.houses[road as usize] += 1;
+        self.seats[p].cash -= price;
+    }
+
+    // The player's squares that could be mortgaged, and what each would raise. Any buildings in
+    // the group have to be sold first.
+    fn mortgageable(&self, p: usize) -> Vec<(Road, i64)> {
+        (0..40)
+            .filter(|r| self.owner[*r] == Some(p) && !self.mortgaged[*r])
+            .map(|r| Road::from_usize(r).unwrap())
+            .filter(|road| {
+                BoardGroup::classify_road(*road)
+                    .members()
+                    .iter()
+                    .all(|m| self.houses[*m as usize] == 0)
+            })
+            .map(|road| (road, mortgage_value(road)))
+            .collect()
+    }
+
+    // Mortgage squares, as the player chooses, until they have at least this much cash, leaving
+    // alone the group they're raising it for. Returns whether they got there.
+    fn raise(&mut self, p: usize, amount: i64, keep: Option<BoardGroup>) -> bool {
+        while self.seats[p].cash < amount {
+            let mortgageable: Vec<(Road, i64)> = self
+                .mortgageable(p)
+                .into_iter()
+                .filter(|(r, _)| Some(BoardGroup::classify_road(*r)) != keep)
+                .collect();
+            if mortgageable.is_empty() {
+                return false;
+            }
+            let shortfall = amount - self.seats[p].cash;
+            let choice = self.seats[p]
+                .strategy
+                .mortgage(&self.view(p), shortfall, &mortgageable);
+            match mortgageable.iter().find(|(r, _)| Some(*r) == choice) {
+                Some((road, value)) => {
+                    self.mortgaged[*road as usize] = true;
+                    self.seats[p].cash += value;
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+
+    // Let the player pay off whichever mortgages they'd like to, at the mortgage plus 10%.
+    fn redeem(&mut self, p: usize) {
+        loop {
+            let mortgaged: Vec<(Road, i64)> = (0..40)
+                .filter(|r| self.owner[*r] == Some(p) && self.mortgaged[*r])
+                .map(|r| Road::from_usize(r).unwrap())
+                .map(|road| (road, interest(mortgage_value(road)) + mortgage_value(road)))
+                .filter(|(_, cost)| *cost <= self.seats[p].cash)
+                .collect();
+            if mortgaged.is_empty() {
+                return;
+            }
+            let choice = self.seats[p].strategy.unmortgage(&self.view(p), &mortgaged);
+            match mortgaged.iter().find(|(r, _)| Some(*r) == choice) {
+                Some((road, cost)) => {
+                    self.mortgaged[*road as usize] = false;
+                    self.seats[p].cash -= cost;
+                }
+                None => return,
+            }
+        }
+    }
+
+    // How many houses and hotels the player has on the board.
+    fn buildings(&self, p: usize) -> (u128, u128) {
+        let mut houses = 0;
+        let mut hotels = 0;
+        for r in 0..40 {
+            if self.owner[r] == Some(p) {
+                match self.houses[r] {
+                    HOTEL => hotels += 1,
+                    n => houses += n as u128,
+                }
+            }
+        }
+        (houses, hotels)
+    }
+
+    fn roll(&mut self) -> RollPair {
+        let r1 = DieRoll::roll(&mut self.rng);
+        let r2 = DieRoll::roll(&mut self.rng);
+        RollPair::new(r1, r2)
+    }
+
+    fn take_turn(&mut self, p: usize) {
+        if let Some(attempts) = self.seats[p].jail {
+            if !self.seats[p].strategy.leave_jail(&self.view(p)) {
+                // out on a double, or pay (or use a card) after the third failed attempt. Either
+                // way we move by this roll, and don't roll again.
+                let roll = self.roll();
+                if roll.is_double() {
+                    self.seats[p].jail = None;
+                } else if attempts == 2 {
+                    self.leave_jail(p);
+                } else {
+                    self.seats[p].jail = Some(attempts + 1);
+                    return;
+                }
+                if !self.seats[p].bankrupt {
+                    self.advance(p, roll);
+                }
+                return;
+            }
+
+            // otherwise leave straight away, with a card if we have one, and take a normal turn
+            self.leave_jail(p);
+            if self.seats[p].bankrupt {
+                return;
+            }
+        }
+
+        // a double earns another roll, but the third in a row goes straight to jail
+        for doubles in 0..3 {
+            let roll = self.roll();
+            let again = roll.is_double();
+
+            if again && doubles == 2 {
+                self.go_to_jail(p);
+                break;
+            }
+
+            // being sent to jail ends the turn, doubles or not
+            if self.advance(p, roll) || self.seats[p].bankrupt || !again {
+                break;
+            }
+        }
+    }
+
+    // Use a held card to get out of jail, or pay the fine.
+    fn leave_jail(&mut self, p: usize) {
+        self.seats[p].jail = None;
+        match self.seats[p].jailfree.pop() {
+            Some(Pile::Chance) => self.chance.put_back(CardAction::GetOutOfJailFree),
+            Some(Pile::CommunityChest) => {
+                self.community_chest.put_back(CardAction::GetOutOfJailFree)
+            }
+            None => self.pay(p, Creditor::Bank, JAIL_FINE),
+        }
+    }
+
+    fn go_to_jail(&mut self, p: usize) {
+        self.seats[p].position = Road::Jail;
+        self.seats[p].jail = Some(0);
+    }
+
+    // Move forwards to a square, collecting a salary if we pass Go on the way.
+    fn move_to(&mut self, p: usize, to: Road) {
+        if (to as u8) <= (self.seats[p].position as u8) {
+            self.seats[p].cash += SALARY;
+        }
+        self.seats[p].position = to;
+    }
+
+    // Move by a roll, and deal with wherever we end up. Returns true if we were sent to jail.
+    fn advance(&mut self, p: usize, roll: RollPair) -> bool {
+        let to = self.seats[p].position.transition(roll);
+        self.move_to(p, to);
+        self.land(p, &roll)
+    }
+
+    // Deal with the square we're stood on. Returns true if we were sent to jail.
+    fn land(&mut self, p: usize, roll: &RollPair) -> bool {
+        let pos = self.seats[p].position;
+        match BoardGroup::classify_road(pos) {
+            BoardGroup::Jail if pos == Road::GotoJail => {
+                self.go_to_jail(p);
+                true
+            }
+            BoardGroup::Tax => {
+                self.pay(p, Creditor::Bank, pos.tax() as i64);
+                false
+            }
+            BoardGroup::Chance => {
+                let card = self.chance.take();
+                if card == CardAction::GetOutOfJailFree {
+                    self.seats[p].jailfree.push(Pile::Chance);
+                } else {
+                    self.chance.put_back(card);
+                }
+                self.apply_card(p, card, roll)
+            }
+            BoardGroup::CommunityChest => {
+                let card = self.community_chest.take();
+                if card == CardAction::GetOutOfJailFree {
+                    self.seats[p].jailfree.push(Pile::CommunityChest);
+                } else {
+                    self.community_chest.put_back(card);
+                }
+                self.apply_card(p, card, roll)
+            }
+            _ => {
+                if let Some(price) = pos.price() {
+                    match self.owner[pos as usize] {
+                        None if self.seats[p].cash >= price as i64
+                            && self.seats[p].strategy.buy(&self.view(p), pos) =>
+                        {
+                            self.seats[p].cash -= price as i64;
+                            self.owner[pos as usize] = Some(p);
+                        }
+                        None if self.auction != Auction::Off => self.sell(p, pos),
+                        // no rent is due on a mortgaged square
+                        Some(owner) if owner != p && !self.mortgaged[pos as usize] => {
+                            let rent = self.rent_for(pos, owner, roll);
+                            self.rent[pos as usize] += rent as u128;
+                            self.pay(p, Creditor::Player(owner), rent);
+                        }
+                        _ => {}
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    // Auction a square to everyone still in the game: one the lander didn't buy, the lander
+    // included, or one left by a player who went bankrupt to the bank.
+    fn sell(&mut self, lander: usize, road: Road) {
+        // bids can't be more than the bidder has, and go round the table from the lander, so that
+        // ties go to whoever bid first
+        let seats = self.seats.len();
+        let bids = (0..seats)
+            .map(|i| (lander + i) % seats)
+            .filter(|q| !self.seats[*q].bankrupt)
+            .map(|q| {
+                let bid = self.seats[q]
+                    .strategy
+                    .bid(&self.view(q), road, self.auction);
+                (q, bid.min(self.seats[q].cash))
+            })
+            .collect();
+
+        if let Some((winner, price)) = settle(self.auction, bids, MIN_BID) {
+            self.seats[winner].cash -= price;
+            self.owner[road as usize] = Some(winner);
+            self.auctions.push((road, price));
+        }
+    }
+
+    fn apply_card(&mut self, p: usize, action: CardAction, roll: &RollPair) -> bool {
+        match action {
+            CardAction::GoToJail => {
+                self.go_to_jail(p);
+                return true;
+            }
+            CardAction::AdvanceTo(to) => {
+                self.move_to(p, to);
+                return self.land(p, roll);
+            }
+            CardAction::AdvanceToNearest(group) => {
+                let to = action.destination(self.seats[p].position).unwrap();
+                self.move_to(p, to);
+                match self.owner[to as usize] {
+                    // an owned one is charged at twice the station rent, or ten times a fresh
+                    // roll for a utility
+                    Some(owner) if owner != p && !self.mortgaged[to as usize] => {
+                        let rent = if group == BoardGroup::Utility {
+                            10 * self.roll().eval() as i64
+                        } else {
+                            2 * self.rent_for(to, owner, roll)
+                        };
+                        self.rent[to as usize] += rent as u128;
+                        self.pay(p, Creditor::Player(owner), rent);
+                    }
+                    _ => return self.land(p, roll),
+                }
+            }
+            CardAction::GoBackTo(_) | CardAction::GoBack(_) => {
+                let pos = self.seats[p].position;
+                self.seats[p].position = action.destination(pos).unwrap();
+                return self.land(p, roll);
+            }
+            CardAction::CollectFromEachPlayer(n) => {
+                for other in 0..self.seats.len() {
+                    if other != p && !self.seats[other].bankrupt {
+                        self.pay(other, Creditor::Player(p), n as i64);
+                    }
+                }
+            }
+            CardAction::PayEachPlayer(n) => {
+                for other in 0..self.seats.len() {
+                    if other != p && !self.seats[other].bankrupt && !self.seats[p].bankrupt {
+                        self.pay(p, Creditor::Player(other), n as i64);
+                    }
+                }
+            }
+            CardAction::Pay(_) => {
+                let amount = -action.cash(0, 0, 0) as i64;
+                self.pay(p, Creditor::Bank, amount);
+            }
+            CardAction::Repairs { .. } => {
+                let (houses, hotels) = self.buildings(p);
+                let amount = -action.cash(houses, hotels, 0) as i64;
+                self.pay(p, Creditor::Bank, amount);
+            }
+            CardAction::Collect(_) => {
+                self.seats[p].cash += action.cash(0, 0, 0) as i64;
+            }
+            CardAction::GetOutOfJailFree => {}
+        }
+        false
+    }
+
+    // Rent by the houses on the square, or base rent doubled for a complete set with none; stations
+    // by how many the owner holds; utilities by how many, and the dice.
+    fn rent_for(&self, road: Road, owner: usize, roll: &RollPair) -> i64 {
+        let group = BoardGroup::classify_road(road);
+        let owned = group
+            .members()
+            .iter()
+            .filter(|r| self.owner[**r as usize] == Some(owner))
+            .count() as u8;
+        let holding = Holding {
+            houses: self.houses[road as usize],
+            stations: if group == BoardGroup::Station {
+                owned
+            } else {
+                0
+            },
+            utilities: if group == BoardGroup::Utility {
+                owned
+            } else {
+                0
+            },
+        };
+        let rent = road.rent();
+        let amount = rent.charge(holding, roll);
+        match rent {
+            Rent::Income { .. } if holding.houses == 0 && self.monopoly(owner, group) => {
+                2 * amount as i64
+            }
+            _ => amount as i64,
+        }
+    }
+
+    // Sell one building back to the bank for half what it cost, from the most developed square
+    // so that the rest stay even. A hotel is broken back down into four houses, unless the bank
+    // is short of them, when the whole group has to go. Returns whether there was anything to
+    // sell.
+    fn sell_building(&mut self, p: usize) -> bool {
+        let road = match (0..40)
+            .filter(|r| self.owner[*r] == Some(p) && self.houses[*r] > 0)
+            .max_by_key(|r| self.houses[*r])
+        {
+            Some(r) => Road::from_usize(r).unwrap(),
+            None => return false,
+        };
+        let group = BoardGroup::classify_road(road);
+        let half = group.house_cost().unwrap() as i64 / 2;
+
+        if self.houses[road as usize] == HOTEL && self.bank_houses < HOTEL - 1 {
+            for member in group.members() {
+                let r = member as usize;
+                self.seats[p].cash += half * self.houses[r] as i64;
+                match self.houses[r] {
+                    HOTEL => self.bank_hotels += 1,
+                    n => self.bank_houses += n,
+                }
+                self.houses[r] = 0;
+            }
+        } else {
+            if self.houses[road as usize] == HOTEL {
+                self.bank_hotels += 1;
+                self.bank_houses -= HOTEL - 1;
+            } else {
+                self.bank_houses += 1;
+            }
+            self.houses[road as usize] -= 1;
+            self.seats[p].cash += half;
+        }
+        true
+    }
+
+    // Raise the cash for a debt by any means: the player's own choice of mortgages first, then
+    // selling off buildings, and at the last mortgaging whatever is left. Returns whether it was
+    // enough.
+    fn liquidate(&mut self, p: usize, amount: i64) -> bool {
+        while !self.raise(p, amount, None) {
+            if self.sell_building(p) {
+                continue;
+            }
+            match self.mortgageable(p).iter().min_by_key(|(_, value)| *value) {
+                Some((road, value)) => {
+                    self.mortgaged[*road as usize] = true;
+                    self.seats[p].cash += value;
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+
+    // Pay up, selling and mortgaging what we have to. If we still can't cover it all we're
+    // bankrupt, and the creditor gets whatever cash is left.
+    fn pay(&mut self, p: usize, to: Creditor, amount: i64) {
+        self.liquidate(p, amount);
+        let paid = amount.min(self.seats[p].cash);
+        self.seats[p].cash -= paid;
+        if let Creditor::Player(c) = to {
+            self.seats[c].cash += paid;
+        }
+        if paid < amount {
+            self.bankrupt(p, to);
+        }
+    }
+
+    // Hand over everything a bankrupt player has left, which by now is only mortgaged squares and
+    // cards: to the player they owe, who takes on the mortgages, or if it's the bank, to auction
+    // with the mortgages cleared.
+    fn bankrupt(&mut self, p: usize, to: Creditor) {
+        self.seats[p].bankrupt = true;
+        self.bankruptcies.push(Bankruptcy {
+            player: p,
+            square: self.seats[p].position,
+            creditor: to,
+        });
+
+        let squares: Vec<usize> = (0..40).filter(|r| self.owner[*r] == Some(p)).collect();
+        let cards = std::mem::take(&mut self.seats[p].jailfree);
+        match to {
+            Creditor::Player(c) => {
+                self.seats[c].jailfree.extend(cards);
+                for r in squares.iter() {
+                    self.owner[*r] = Some(c);
+                }
+                // taking on a mortgaged square means paying the bank its interest straight away,
+                // unless the creditor is the last one left, and the game is over. Otherwise the
+                // fee could bankrupt them too, and leave nobody to win.
+                if self.active() == 1 {
+                    return;
+                }
+                for r in squares {
+                    if self.mortgaged[r] && !self.seats[c].bankrupt {
+                        let fee = interest(mortgage_value(Road::from_usize(r).unwrap()));
+                        self.pay(c, Creditor::Bank, fee);
+                    }
+                }
+            }
+            Creditor::Bank => {
+                for pile in cards {
+                    match pile {
+                        Pile::Chance => self.chance.put_back(CardAction::GetOutOfJailFree),
+                        Pile::CommunityChest => {
+                            self.community_chest.put_back(CardAction::GetOutOfJailFree)
+                        }
+                    }
+                }
+                for r in squares.iter() {
+                    self.owner[*r] = None;
+                    self.mortgaged[*r] = false;
+                }
+                if self.auction != Auction::Off {
+                    for r in squares {
+                        self.sell(p, Road::from_usize(r).unwrap());
+                    }
+                }
+            }
+        }
+    }
+}
+
+// What the bank lends against a square: half its price.
+fn mortgage_value(road: Road) -> i64 {
+    road.price().unwrap() as i64 / 2
+}
+
+// The bank's 10% on a mortgage, rounded up.
+fn interest(mortgage: i64) -> i64 {
+    (mortgage + 9) / 10
+}
+
+// Who wins an auction, given everyone's bids in the order they were made, and what they pay. Bids
+// under the reserve don't count, and ties go to whoever bid first.
+fn settle(auction: Auction, mut bids: Vec<(usize, i64)>, reserve: i64) -> Option<(usize, i64)> {
+    bids.retain(|(_, bid)| *bid >= reserve);
+    bids.sort_by_key(|(_, bid)| std::cmp::Reverse(*bid));
+    let (winner, top) = *bids.first()?;
+    let price = match auction {
+        // the bidding stops one step past the runner up's limit
+        Auction::English => bids.get(1).map_or(reserve, |(_, bid)| (bid + 1).min(top)),
+        Auction::Sealed => top,
+        Auction::Off => unreachable!(),
+    };
+    Some((winner, price))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy;
+    use crate::JailStrategy;
+
+    fn game(players: usize, auction: Auction) -> Game<StdRng> {
+        let strategies = (0..players)
+            .map(|_| strategy::named("always", JailStrategy::LongStay).unwrap())
+            .collect();
+        Game::new(strategies, auction, StdRng::seed_from_u64(1))
+    }
+
+    fn give(g: &mut Game<StdRng>, p: usize, roads: &[Road]) {
+        for r in roads {
+            g.owner[*r as usize] = Some(p);
+        }
+    }
+
+    // The bank's stock and what's on the board always add up to everything there is.
+    fn assert_stock(g: &Game<StdRng>) {
+        let houses: u8 = g.houses.iter().filter(|h| **h < HOTEL).sum();
+        let hotels = g.houses.iter().filter(|h| **h == HOTEL).count() as u8;
+        assert_eq!(g.bank_houses + houses, HOUSES);
+        assert_eq!(g.bank_hotels + hotels, HOTELS);
+    }
+
+    #[test]
+    fn english_auctions_go_one_past_the_runner_up() {
+        assert_eq!(
+            settle(Auction::English, vec![(0, 100), (1, 80), (2, 50)], 1),
+            Some((0, 81))
+        );
+        // a lone bidder pays the reserve, and a tie goes to whoever bid first at their limit
+        assert_eq!(settle(Auction::English, vec![(2, 100)], 10), Some((2, 10)));
+        assert_eq!(
+            settle(Auction::English, vec![(1, 100), (0, 100)], 1),
+            Some((1, 100))
+        );
+    }
+
+    #[test]
+    fn sealed_bids_pay_what_they_wrote() {
+        assert_eq!(
+            settle(Auction::Sealed, vec![(0, 80), (1, 100)], 1),
+            Some((1, 100))
+        );
+        assert_eq!(
+            settle(Auction::Sealed, vec![(2, 90), (0, 90)], 1),
+            Some((2, 90))
+        );
+    }
+
+    #[test]
+    fn bids_under_the_reserve_dont_count() {
+        assert_eq!(settle(Auction::English, vec![(0, 40), (1, 49)], 50), None);
+        assert_eq!(
+            settle(Auction::Sealed, vec![(0, 40), (1, 60)], 50),
+            Some((1, 60))
+        );
+    }
+
+    #[test]
+    fn mortgages_are_half_the_price_with_interest_rounded_up() {
+        assert_eq!(mortgage_value(Road::Mayfair), 200);
+        assert_eq!(mortgage_value(Road::OldKentRoad), 30);
+        assert_eq!(interest(200), 20);
+        assert_eq!(interest(30), 3);
+        assert_eq!(interest(35), 4);
+    }
+
+    #[test]
+    fn houses_go_up_evenly() {
+        let mut g = game(2, Auction::English);
+        assert!(g.buildable(0).is_empty());
+
+        give(&mut g, 0, &[Road::OldKentRoad, Road::WhiteChapelRoad]);
+        assert_eq!(
+            g.buildable(0),
+            vec![(Road::OldKentRoad, 50), (Road::WhiteChapelRoad, 50)]
+        );
+
+        g.houses[Road::OldKentRoad as usize] = 1;
+        assert_eq!(g.buildable(0), vec![(Road::WhiteChapelRoad, 50)]);
+
+        g.houses[Road::OldKentRoad as usize] = HOTEL;
+        g.houses[Road::WhiteChapelRoad as usize] = HOTEL;
+        assert!(g.buildable(0).is_empty());
+    }
+
+    #[test]
+    fn nothing_is_built_on_a_set_with_a_mortgage() {
+        let mut g = game(2, Auction::English);
+        give(&mut g, 0, &[Road::OldKentRoad, Road::WhiteChapelRoad]);
+        g.mortgaged[Road::WhiteChapelRoad as usize] = true;
+        assert!(g.buildable(0).is_empty());
+    }
+
+    #[test]
+    fn building_and_selling_keep_the_bank_stock() {
+        let mut g = game(2, Auction::English);
+        let streets: Vec<Road> = (0..40)
+            .map(|r| Road::from_usize(r).unwrap())
+            .filter(|r| BoardGroup::classify_road(*r).house_cost().is_some())
+            .collect();
+        give(&mut g, 0, &streets);
+        g.seats[0].cash = 1_000_000;
+
+        // build until the bank has nothing left that could go up
+        loop {
+            let next = g.buildable(0).into_iter().find(|(r, _)| {
+                if g.houses[*r as usize] == HOTEL - 1 {
+                    g.bank_hotels > 0
+                } else {
+                    g.bank_houses > 0
+                }
+            });
+            match next {
+                Some((road, cost)) => g.build(0, road, cost),
+                None => break,
+            }
+            assert_stock(&g);
+        }
+        assert_eq!(g.bank_houses, 0);
+
+        while g.sell_building(0) {
+            assert_stock(&g);
+        }
+        assert_eq!((g.bank_houses, g.bank_hotels), (HOUSES, HOTELS));
+    }
+
+    #[test]
+    fn a_creditor_takes_over_the_mortgages_and_pays_the_fee() {
+        let mut g = game(3, Auction::English);
+        give(&mut g, 1, &[Road::OldKentRoad, Road::Mayfair]);
+        g.mortgaged[Road::OldKentRoad as usize] = true;
+        g.seats[1].jailfree.push(Pile::Chance);
+
+        g.bankrupt(1, Creditor::Player(0));
+        assert!(g.seats[1].bankrupt);
+        assert_eq!(g.owner[Road::OldKentRoad as usize], Some(0));
+        assert_eq!(g.owner[Road::Mayfair as usize], Some(0));
+        assert!(g.mortgaged[Road::OldKentRoad as usize]);
+        assert_eq!(g.seats[0].cash, STARTING_CASH - interest(30));
+        assert_eq!(g.seats[0].jailfree, vec![Pile::Chance]);
+    }
+
+    #[test]
+    fn the_last_player_standing_owes_no_fee() {
+        let mut g = game(2, Auction::English);
+        give(&mut g, 1, &[Road::Mayfair]);
+        g.mortgaged[Road::Mayfair as usize] = true;
+        g.seats[0].cash = 0;
+
+        g.bankrupt(1, Creditor::Player(0));
+        assert!(!g.seats[0].bankrupt);
+        assert_eq!(g.leader(), 0);
+    }
+
+    #[test]
+    fn the_bank_auctions_off_what_a_bankrupt_player_had() {
+        let mut g = game(3, Auction::English);
+        give(&mut g, 1, &[Road::Mayfair]);
+        g.mortgaged[Road::Mayfair as usize] = true;
+
+        g.bankrupt(1, Creditor::Bank);
+        // both the others bid the list price, and the first to bid after the bankrupt player wins
+        assert!(!g.mortgaged[Road::Mayfair as usize]);
+        assert_eq!(g.owner[Road::Mayfair as usize], Some(2));
+        assert_eq!(g.auctions, vec![(Road::Mayfair, 400)]);
+        assert_eq!(g.seats[2].cash, STARTING_CASH - 400);
+    }
+
+    #[test]
+    fn without_auctions_the_bank_keeps_it() {
+        let mut g = game(3, Auction::Off);
+        give(&mut g, 1, &[Road::Mayfair]);
+        g.bankrupt(1, Creditor::Bank);
+        assert_eq!(g.owner[Road::Mayfair as usize], None);
+        assert!(g.auctions.is_empty());
+    }
+}
@@ -1,12 +1,34 @@
+#![allow(clippy::enum_variant_names)]
+
 #[macro_use]
 extern crate enum_primitive_derive;
 extern crate num_traits;
 extern crate rand;
 
+mod cards;
+mod cli;
+mod edition;
+mod export;
+mod game;
+mod markov;
+mod stats;
+mod strategy;
+
+use cards::{CardAction, CardEvent, Deck};
+use cli::{Command, Format, Options};
+use edition::{edition, Edition, Kind, Square};
+use game::{Creditor, Game};
+use markov::{group_landings, Chain, IN_JAIL};
 use num_traits::FromPrimitive;
 use rand::prelude::*;
+use stats::{Batches, Distribution};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-#[derive(Primitive)]
+#[derive(Primitive, Clone, Copy, PartialEq, Eq)]
 enum DieRoll {
     One = 1,
     Two = 2,
@@ -23,6 +45,7 @@ impl DieRoll {
     }
 }
 
+#[derive(Clone, Copy)]
 struct RollPair {
     r1: DieRoll,
     r2: DieRoll,
@@ -30,11 +53,14 @@ struct RollPair {
 
 impl RollPair {
     fn new(r1: DieRoll, r2: DieRoll) -> RollPair {
-        RollPair { r1: r1, r2: r2 }
+        RollPair { r1, r2 }
     }
-    fn eval(self) -> u8 {
+    fn eval(&self) -> u8 {
         self.r1 as u8 + self.r2 as u8
     }
+    fn is_double(&self) -> bool {
+        self.r1 == self.r2
+    }
 }
 
 enum Rent {
@@ -46,13 +72,38 @@ enum Rent {
         h4: u128,
         ho: u128,
     },
-    Station,
-    Utility,
+    // by how many stations the owner holds
+    Station([u128; 4]),
+    // the dice multiplier, by how many utilities the owner holds
+    Utility([u128; 2]),
     NA,
 }
 
+// What the owner of a square holds that its rent depends on: the houses on it (5 for a
+// hotel), and how many stations and utilities they have.
+#[derive(Clone, Copy)]
+struct Holding {
+    houses: u8,
+    stations: u8,
+    utilities: u8,
+}
+
+impl Holding {
+    // A hotel on every street, and every station and utility, as the single token simulations
+    // assume when counting what a square could earn.
+    fn full() -> Holding {
+        Holding {
+            houses: 5,
+            stations: 4,
+            utilities: 2,
+        }
+    }
+}
+
 impl Rent {
-    fn expected(&self, visits: u128) -> String {
+    // The rent on a square with this many houses (5 for a hotel), when its owner holds this many
+    // stations and utilities, and the player got there with this dice total.
+    fn amount(&self, houses: u8, stations: u8, utilities: u8, dice: u8) -> u128 {
         match self {
             Rent::Income {
                 m,
@@ -61,62 +112,46 @@ impl Rent {
                 h3,
                 h4,
                 ho,
-            } => format!(
-                " {} / {} / {} / {} / {} / {} ",
-                m * visits,
-                h1 * visits,
-                h2 * visits,
-                h3 * visits,
-                h4 * visits,
-                ho * visits
-            ),
-            Rent::Station => format!(
-                " {} / {} / {} / {} ",
-                visits * 25,
-                visits * 50,
-                visits * 100,
-                visits * 200
-            ),
-            Rent::Utility => format!(" {} / {} ", visits * 8, visits * 120),
-            Rent::NA => String::from(" NA "),
-        }
-    }
-
-    fn max(&self) -> u128 {
-        match self {
-            Rent::Income {
-                m,
-                h1,
-                h2,
-                h3,
-                h4,
-                ho,
-            } => *ho,
-            Rent::Station => 200,
-            Rent::Utility => 120,
-            Rent::NA => 0,
+            } => match houses {
+                0 => *m,
+                1 => *h1,
+                2 => *h2,
+                3 => *h3,
+                4 => *h4,
+                _ => *ho,
+            },
+            Rent::Station(rents) if stations > 0 => rents[stations.min(4) as usize - 1],
+            Rent::Utility(times) if utilities > 0 => {
+                times[utilities.min(2) as usize - 1] * dice as u128
+            }
+            _ => 0,
         }
     }
 
-    fn avg(&self) -> u128 {
+    // Every rent a square can charge, from the least to the most its owner could hold, taking an
+    // average roll of 7 for the utilities.
+    fn levels(&self) -> Vec<u128> {
         match self {
-            Rent::Income {
-                m,
-                h1,
-                h2,
-                h3,
-                h4,
-                ho,
-            } => (m + h1 + h2 + h3 + h4 + ho) / 6,
-            Rent::Station => (25 + 50 + 100 + 200) / 4,
-            Rent::Utility => ((4 * 8) + (10 * 8)) / 2,
-            Rent::NA => 0,
+            Rent::Income { .. } => (0..6).map(|h| self.amount(h, 0, 0, 0)).collect(),
+            Rent::Station(_) => (1..5).map(|s| self.amount(0, s, 0, 0)).collect(),
+            Rent::Utility(_) => (1..3).map(|u| self.amount(0, 0, u, 7)).collect(),
+            Rent::NA => Vec::new(),
         }
     }
+
+    // The rent a player pays when this roll brought them to the square.
+    fn charge(&self, holding: Holding, roll: &RollPair) -> u128 {
+        self.amount(
+            holding.houses,
+            holding.stations,
+            holding.utilities,
+            roll.eval(),
+        )
+    }
 }
 
 #[derive(Primitive, Clone, Copy, PartialEq, Eq, Debug)]
-enum Road {
+pub enum Road {
     Go = 0,
     OldKentRoad = 1,
     CommunityChest1 = 2,
@@ -161,304 +196,66 @@ enum Road {
 
 impl Road {
     fn transition(self, r: RollPair) -> Road {
-        // check to see if we've landed on "GoToJail", in which case, transition to Jail, but don't record extra for jail
-        let pos = match self {
-            Road::GotoJail => Road::Jail,
-            r => r,
-        };
         // Get the self as a u8.
-        let u = pos as u8;
+        let u = self as u8;
         // Add on the roll, and wrap around.
         Road::from_u8((u + r.eval()) % (Road::Mayfair as u8 + 1)).unwrap()
     }
 
+    fn back(self, n: u8) -> Road {
+        let squares = Road::Mayfair as u8 + 1;
+        Road::from_u8((self as u8 + squares - n) % squares).unwrap()
+    }
+
+    // The first square of a group from here on, going round past Go if need be.
+    fn nearest(self, group: BoardGroup) -> Road {
+        let squares = Road::Mayfair as u8 + 1;
+        (1..=squares)
+            .map(|n| Road::from_u8((self as u8 + n) % squares).unwrap())
+            .find(|r| BoardGroup::classify_road(*r) == group)
+            .unwrap_or(self)
+    }
+
+    // What the board in use says about this square.
+    fn square(&self) -> &'static Square {
+        &edition().squares[*self as usize]
+    }
+
     fn as_str(&self) -> &'static str {
-        match self {
-            Road::Go => "Go",
-            Road::OldKentRoad => "OldKentRoad",
-            Road::CommunityChest1 => "CommunityChest1",
-            Road::WhiteChapelRoad => "WhiteChapelRoad",
-            Road::IncomeTax => "IncomeTax",
-            Road::KingsCrossStation => "KingsCrossStation",
-            Road::TheAngelIslington => "TheAngelIslington",
-            Road::Chance1 => "Chance1",
-            Road::EustonRoad => "EustonRoad",
-            Road::PentonvilleRoad => "PentonvilleRoad",
-            Road::Jail => "Jail",
-            Road::PallMall => "PallMall",
-            Road::ElectricCompany => "ElectricCompany",
-            Road::Whitehall => "Whitehall",
-            Road::NorthumberlandAvenue => "NorthumberlandAvenue",
-            Road::MaryleboneStation => "MaryleboneStation",
-            Road::BowStreet => "BowStreet",
-            Road::CommunityChest2 => "CommunityChest2",
-            Road::MarlboroughStreet => "MarlboroughStreet",
-            Road::VineStreet => "VineStreet",
-            Road::FreeParking => "FreeParking",
-            Road::Strand => "Strand",
-            Road::Chance2 => "Chance2",
-            Road::FleetStreet => "FleetStreet",
-            Road::TrafalgarSquare => "TrafalgarSquare",
-            Road::FenchurchStStation => "FenchurchStStation",
-            Road::LeicesterSquare => "LeicesterSquare",
-            Road::CoventryStreet => "CoventryStreet",
-            Road::WaterWorks => "WaterWorks",
-            Road::Picadilly => "Picadilly",
-            Road::GotoJail => "GotoJail",
-            Road::RegentStreet => "RegentStreet",
-            Road::OxfordStreet => "OxfordStreet",
-            Road::CommunityChest3 => "CommunityChest3",
-            Road::BondStreet => "BondStreet",
-            Road::LiverpoolStStation => "LiverpoolStStation",
-            Road::Chance3 => "Chance3",
-            Road::ParkLane => "ParkLane",
-            Road::SuperTax => "SuperTax",
-            Road::Mayfair => "Mayfair",
-        }
+        &self.square().name
     }
 
     fn rent(&self) -> Rent {
-        match self {
-            Road::Go => Rent::NA,
-            Road::OldKentRoad => Rent::Income {
-                m: 2,
-                h1: 10,
-                h2: 30,
-                h3: 90,
-                h4: 160,
-                ho: 250,
-            },
-            Road::CommunityChest1 => Rent::NA,
-            Road::WhiteChapelRoad => Rent::Income {
-                m: 4,
-                h1: 20,
-                h2: 60,
-                h3: 180,
-                h4: 360,
-                ho: 450,
-            },
-            Road::IncomeTax => Rent::NA,
-            Road::KingsCrossStation => Rent::Station,
-            Road::TheAngelIslington => Rent::Income {
-                m: 6,
-                h1: 30,
-                h2: 90,
-                h3: 270,
-                h4: 400,
-                ho: 550,
-            },
-            Road::Chance1 => Rent::NA,
-            Road::EustonRoad => Rent::Income {
-                m: 6,
-                h1: 30,
-                h2: 90,
-                h3: 270,
-                h4: 400,
-                ho: 550,
-            },
-            Road::PentonvilleRoad => Rent::Income {
-                m: 8,
-                h1: 40,
-                h2: 100,
-                h3: 300,
-                h4: 450,
-                ho: 600,
-            },
-            Road::Jail => Rent::NA,
-            Road::PallMall => Rent::Income {
-                m: 10,
-                h1: 50,
-                h2: 150,
-                h3: 450,
-                h4: 625,
-                ho: 750,
-            },
-            Road::ElectricCompany => Rent::Utility,
-            Road::Whitehall => Rent::Income {
-                m: 10,
-                h1: 50,
-                h2: 150,
-                h3: 450,
-                h4: 625,
-                ho: 750,
-            },
-            Road::NorthumberlandAvenue => Rent::Income {
-                m: 12,
-                h1: 60,
-                h2: 180,
-                h3: 500,
-                h4: 700,
-                ho: 900,
-            },
-            Road::MaryleboneStation => Rent::Station,
-            Road::BowStreet => Rent::Income {
-                m: 14,
-                h1: 70,
-                h2: 200,
-                h3: 550,
-                h4: 750,
-                ho: 950,
-            },
-            Road::CommunityChest2 => Rent::NA,
-            Road::MarlboroughStreet => Rent::Income {
-                m: 14,
-                h1: 70,
-                h2: 200,
-                h3: 550,
-                h4: 750,
-                ho: 950,
-            },
-            Road::VineStreet => Rent::Income {
-                m: 16,
-                h1: 80,
-                h2: 220,
-                h3: 600,
-                h4: 800,
-                ho: 1000,
-            },
-            Road::FreeParking => Rent::NA,
-            Road::Strand => Rent::Income {
-                m: 18,
-                h1: 90,
-                h2: 250,
-                h3: 700,
-                h4: 875,
-                ho: 1050,
-            },
-            Road::Chance2 => Rent::NA,
-            Road::FleetStreet => Rent::Income {
-                m: 18,
-                h1: 90,
-                h2: 250,
-                h3: 700,
-                h4: 875,
-                ho: 1050,
-            },
-            Road::TrafalgarSquare => Rent::Income {
-                m: 20,
-                h1: 100,
-                h2: 300,
-                h3: 750,
-                h4: 925,
-                ho: 1100,
-            },
-            Road::FenchurchStStation => Rent::Station,
-            Road::LeicesterSquare => Rent::Income {
-                m: 22,
-                h1: 110,
-                h2: 330,
-                h3: 800,
-                h4: 975,
-                ho: 1150,
-            },
-            Road::CoventryStreet => Rent::Income {
-                m: 22,
-                h1: 110,
-                h2: 330,
-                h3: 800,
-                h4: 975,
-                ho: 1150,
-            },
-            Road::WaterWorks => Rent::Utility,
-            Road::Picadilly => Rent::Income {
-                m: 22,
-                h1: 120,
-                h2: 360,
-                h3: 850,
-                h4: 1025,
-                ho: 1200,
-            },
-            Road::GotoJail => Rent::NA,
-            Road::RegentStreet => Rent::Income {
-                m: 26,
-                h1: 130,
-                h2: 390,
-                h3: 900,
-                h4: 1100,
-                ho: 1275,
-            },
-            Road::OxfordStreet => Rent::Income {
-                m: 26,
-                h1: 130,
-                h2: 390,
-                h3: 900,
-                h4: 1100,
-                ho: 1275,
-            },
-            Road::CommunityChest3 => Rent::NA,
-            Road::BondStreet => Rent::Income {
-                m: 28,
-                h1: 150,
-                h2: 450,
-                h3: 1000,
-                h4: 1200,
-                ho: 1400,
-            },
-            Road::LiverpoolStStation => Rent::Station,
-            Road::Chance3 => Rent::NA,
-            Road::ParkLane => Rent::Income {
-                m: 35,
-                h1: 175,
-                h2: 500,
-                h3: 1100,
-                h4: 1300,
-                ho: 1500,
-            },
-            Road::SuperTax => Rent::NA,
-            Road::Mayfair => Rent::Income {
-                m: 50,
-                h1: 200,
-                h2: 600,
-                h3: 1400,
-                h4: 1700,
-                ho: 2000,
+        let rent = &self.square().rent;
+        match self.square().kind {
+            Kind::Street => Rent::Income {
+                m: rent[0],
+                h1: rent[1],
+                h2: rent[2],
+                h3: rent[3],
+                h4: rent[4],
+                ho: rent[5],
             },
+            Kind::Station => Rent::Station([rent[0], rent[1], rent[2], rent[3]]),
+            Kind::Utility => Rent::Utility([rent[0], rent[1]]),
+            _ => Rent::NA,
         }
     }
 
+    // The price to buy the square from the bank, or None if it can't be owned.
+    fn price(&self) -> Option<u128> {
+        self.square().price
+    }
+
+    // What landing here costs, if it's a tax square.
+    fn tax(&self) -> u128 {
+        self.square().tax
+    }
+
+    // The price of the square, and of a hotel on it if it's a street.
     fn expenses(&self) -> u128 {
-        match self {
-            Road::Go => 0,
-            Road::OldKentRoad => 60 + (4 * 30) + 30,
-            Road::CommunityChest1 => 0,
-            Road::WhiteChapelRoad => 60 + (4 * 30) + 30,
-            Road::IncomeTax => 0,
-            Road::KingsCrossStation => 200,
-            Road::TheAngelIslington => 100 + (4 * 50) + 50,
-            Road::Chance1 => 0,
-            Road::EustonRoad => 100 + (4 * 50) + 50,
-            Road::PentonvilleRoad => 120 + (4 * 50) + 50,
-            Road::Jail => 0,
-            Road::PallMall => 140 + (4 * 100) + 100,
-            Road::ElectricCompany => 150,
-            Road::Whitehall => 140 + (4 * 100) + 100,
-            Road::NorthumberlandAvenue => 160 + (4 * 100) + 100,
-            Road::MaryleboneStation => 200,
-            Road::BowStreet => 180 + (4 * 100) + 100,
-            Road::CommunityChest2 => 0,
-            Road::MarlboroughStreet => 180 + (4 * 100) + 100,
-            Road::VineStreet => 200 + (4 * 100) + 100,
-            Road::FreeParking => 0,
-            Road::Strand => 220 + (4 * 150) + 150,
-            Road::Chance2 => 0,
-            Road::FleetStreet => 220 + (4 * 150) + 150,
-            Road::TrafalgarSquare => 240 + (4 * 150) + 150,
-            Road::FenchurchStStation => 200,
-            Road::LeicesterSquare => 260 + (4 * 150) + 150,
-            Road::CoventryStreet => 150 + (4 * 150) + 150,
-            Road::WaterWorks => 150,
-            Road::Picadilly => 280 + (4 * 140) + 140,
-            Road::GotoJail => 0,
-            Road::RegentStreet => 300 + (4 * 150) + 150,
-            Road::OxfordStreet => 300 + (4 * 150) + 150,
-            Road::CommunityChest3 => 0,
-            Road::BondStreet => 320 + (4 * 160) + 160,
-            Road::LiverpoolStStation => 200,
-            Road::Chance3 => 0,
-            Road::ParkLane => 350 + (4 * 200) + 200,
-            Road::SuperTax => 0,
-            Road::Mayfair => 400 + (4 * 200) + 200,
-        }
+        let house = BoardGroup::classify_road(*self).house_cost().unwrap_or(0);
+        self.price().unwrap_or(0) + 5 * house
     }
 }
 
@@ -482,49 +279,26 @@ enum BoardGroup {
 }
 
 impl BoardGroup {
+    fn members(&self) -> Vec<Road> {
+        (0..40)
+            .map(|r| Road::from_usize(r).unwrap())
+            .filter(|r| BoardGroup::classify_road(*r) == *self)
+            .collect()
+    }
+
     fn classify_road(r: Road) -> BoardGroup {
-        match r {
-            Road::Go => BoardGroup::GoParking,
-            Road::OldKentRoad => BoardGroup::Brown,
-            Road::CommunityChest1 => BoardGroup::CommunityChest,
-            Road::WhiteChapelRoad => BoardGroup::Brown,
-            Road::IncomeTax => BoardGroup::Tax,
-            Road::KingsCrossStation => BoardGroup::Station,
-            Road::TheAngelIslington => BoardGroup::LightBlue,
-            Road::Chance1 => BoardGroup::Chance,
-            Road::EustonRoad => BoardGroup::LightBlue,
-            Road::PentonvilleRoad => BoardGroup::LightBlue,
-            Road::Jail => BoardGroup::Jail,
-            Road::PallMall => BoardGroup::Pink,
-            Road::ElectricCompany => BoardGroup::Utility,
-            Road::Whitehall => BoardGroup::Pink,
-            Road::NorthumberlandAvenue => BoardGroup::Pink,
-            Road::MaryleboneStation => BoardGroup::Station,
-            Road::BowStreet => BoardGroup::Orange,
-            Road::CommunityChest2 => BoardGroup::CommunityChest,
-            Road::MarlboroughStreet => BoardGroup::Orange,
-            Road::VineStreet => BoardGroup::Orange,
-            Road::FreeParking => BoardGroup::GoParking,
-            Road::Strand => BoardGroup::Red,
-            Road::Chance2 => BoardGroup::Chance,
-            Road::FleetStreet => BoardGroup::Red,
-            Road::TrafalgarSquare => BoardGroup::Red,
-            Road::FenchurchStStation => BoardGroup::Station,
-            Road::LeicesterSquare => BoardGroup::Yellow,
-            Road::CoventryStreet => BoardGroup::Yellow,
-            Road::WaterWorks => BoardGroup::Utility,
-            Road::Picadilly => BoardGroup::Yellow,
-            Road::GotoJail => BoardGroup::Jail,
-            Road::RegentStreet => BoardGroup::Green,
-            Road::OxfordStreet => BoardGroup::Green,
-            Road::CommunityChest3 => BoardGroup::CommunityChest,
-            Road::BondStreet => BoardGroup::Green,
-            Road::LiverpoolStStation => BoardGroup::Station,
-            Road::Chance3 => BoardGroup::Chance,
-            Road::ParkLane => BoardGroup::DarkBlue,
-            Road::SuperTax => BoardGroup::Tax,
-            Road::Mayfair => BoardGroup::DarkBlue,
-        }
+        r.square().group
+    }
+
+    // Whether the squares in this group can be bought.
+    fn ownable(&self) -> bool {
+        self.members().iter().any(|r| r.price().is_some())
+    }
+
+    // What each house costs to build in this group, and a hotel on top of four houses, if it
+    // can be built on at all.
+    fn house_cost(&self) -> Option<u128> {
+        edition().house_cost(*self)
     }
 
     fn as_str(&self) -> &'static str {
@@ -548,187 +322,1089 @@ impl BoardGroup {
     }
 
     fn expenses(&self) -> u128 {
+        self.members().iter().map(|r| r.expenses()).sum()
+    }
+}
+
+struct Board(pub [u128; 40]);
+
+// An independent random stream for each player or worker, derived from the seed for the run so
+// that the whole run can be reproduced from that one number.
+fn stream(seed: u64, index: u64) -> StdRng {
+    // splitmix64, so that neighbouring indices give unrelated seeds
+    let mut z = seed.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    StdRng::seed_from_u64(z ^ (z >> 31))
+}
+
+// How a player gets out of jail.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum JailStrategy {
+    // pay the fine straight away, and take a normal turn
+    ShortStay,
+    // sit tight and try for doubles, only paying once the third attempt fails
+    LongStay,
+}
+
+impl JailStrategy {
+    fn as_str(&self) -> &'static str {
         match self {
-            BoardGroup::Brown => Road::OldKentRoad.expenses() + Road::WhiteChapelRoad.expenses(),
-            BoardGroup::LightBlue => {
-                Road::TheAngelIslington.expenses()
-                    + Road::EustonRoad.expenses()
-                    + Road::PentonvilleRoad.expenses()
-            }
-            BoardGroup::Pink => {
-                Road::PallMall.expenses()
-                    + Road::Whitehall.expenses()
-                    + Road::NorthumberlandAvenue.expenses()
+            JailStrategy::ShortStay => "ShortStay",
+            JailStrategy::LongStay => "LongStay",
+        }
+    }
+}
+
+// Everything we count while playing, kept apart from the players so that counts from many
+// players, batches and threads can be added together.
+struct Tally {
+    games: u64,
+    turns: u64,
+    visits: Board,
+    injail: u128,
+    groups: [u128; 15],
+    income: Board,
+    groupincome: [u128; 15],
+    // net profit per game on each square and group, once fully developed
+    profit: Vec<Distribution>,
+    groupprofit: Vec<Distribution>,
+    // games in which each square and group made the most profit, in WIN_SHARE parts of a game so
+    // that ties can share the credit exactly
+    winner: [u128; 40],
+    groupwinner: [u128; 15],
+    // landing percentages for each square (and in jail, last) and group, and win percentages for
+    // each square and group, one value per batch of games, for their standard errors
+    visitbatches: Vec<Batches>,
+    groupbatches: Vec<Batches>,
+    winbatches: Vec<Batches>,
+    groupwinbatches: Vec<Batches>,
+    fines: u128,
+    cardcash: i128,
+    // cash from the cards drawn on each square, paid out and collected
+    cardincome: Board,
+    cardexpense: Board,
+}
+
+impl Tally {
+    fn new() -> Tally {
+        Tally {
+            games: 0,
+            turns: 0,
+            visits: Board([0; 40]),
+            injail: 0,
+            groups: [0; 15],
+            income: Board([0; 40]),
+            groupincome: [0; 15],
+            profit: vec![Distribution::new(); 40],
+            groupprofit: vec![Distribution::new(); 15],
+            winner: [0; 40],
+            groupwinner: [0; 15],
+            visitbatches: vec![Batches::new(); 41],
+            groupbatches: vec![Batches::new(); 15],
+            winbatches: vec![Batches::new(); 40],
+            groupwinbatches: vec![Batches::new(); 15],
+            fines: 0,
+            cardcash: 0,
+            cardincome: Board([0; 40]),
+            cardexpense: Board([0; 40]),
+        }
+    }
+
+    // Add another tally's counts to our own.
+    fn merge(&mut self, other: &Tally) {
+        let boards = [
+            (&mut self.visits, &other.visits),
+            (&mut self.income, &other.income),
+            (&mut self.cardincome, &other.cardincome),
+            (&mut self.cardexpense, &other.cardexpense),
+        ];
+        for (mine, theirs) in boards {
+            for (m, t) in mine.0.iter_mut().zip(theirs.0.iter()) {
+                *m += t;
             }
-            BoardGroup::Orange => {
-                Road::BowStreet.expenses()
-                    + Road::MarlboroughStreet.expenses()
-                    + Road::VineStreet.expenses()
+        }
+        let groups = [
+            (&mut self.groups, &other.groups),
+            (&mut self.groupincome, &other.groupincome),
+        ];
+        for (mine, theirs) in groups {
+            for (m, t) in mine.iter_mut().zip(theirs.iter()) {
+                *m += t;
             }
-            BoardGroup::Red => {
-                Road::Strand.expenses()
-                    + Road::FleetStreet.expenses()
-                    + Road::TrafalgarSquare.expenses()
+        }
+        for (m, t) in self.profit.iter_mut().zip(other.profit.iter()) {
+            m.merge(t);
+        }
+        for (m, t) in self.groupprofit.iter_mut().zip(other.groupprofit.iter()) {
+            m.merge(t);
+        }
+        for (m, t) in self.winner.iter_mut().zip(other.winner.iter()) {
+            *m += t;
+        }
+        for (m, t) in self.groupwinner.iter_mut().zip(other.groupwinner.iter()) {
+            *m += t;
+        }
+        let batches = [
+            (&mut self.visitbatches, &other.visitbatches),
+            (&mut self.groupbatches, &other.groupbatches),
+            (&mut self.winbatches, &other.winbatches),
+            (&mut self.groupwinbatches, &other.groupwinbatches),
+        ];
+        for (mine, theirs) in batches {
+            for (m, t) in mine.iter_mut().zip(theirs.iter()) {
+                m.merge(t);
             }
-            BoardGroup::Yellow => {
-                Road::LeicesterSquare.expenses()
-                    + Road::CoventryStreet.expenses()
-                    + Road::Picadilly.expenses()
+        }
+        self.games += other.games;
+        self.turns += other.turns;
+        self.injail += other.injail;
+        self.fines += other.fines;
+        self.cardcash += other.cardcash;
+    }
+
+    // Record this tally's percentages as those of one batch. Only makes sense on a tally of a
+    // single batch, before it is merged into the total.
+    fn end_batch(&mut self) {
+        let turns = self.turns as f64;
+        let games = self.games as f64;
+        let visits = self.visits.0.iter().chain(std::iter::once(&self.injail));
+        for (b, visits) in self.visitbatches.iter_mut().zip(visits) {
+            b.add(100.0 * *visits as f64 / turns);
+        }
+        for (b, visits) in self.groupbatches.iter_mut().zip(self.groups.iter()) {
+            b.add(100.0 * *visits as f64 / turns);
+        }
+        for (b, wins) in self.winbatches.iter_mut().zip(self.winner.iter()) {
+            b.add(100.0 * games_won(*wins) / games);
+        }
+        for (b, wins) in self.groupwinbatches.iter_mut().zip(self.groupwinner.iter()) {
+            b.add(100.0 * games_won(*wins) / games);
+        }
+    }
+
+    // The widest 95% confidence interval half-width of any landing or win percentage, which is
+    // infinite until there are two batches.
+    fn worst_half_width(&self) -> f64 {
+        self.visitbatches
+            .iter()
+            .chain(self.groupbatches.iter())
+            .chain(self.winbatches.iter())
+            .chain(self.groupwinbatches.iter())
+            .map(|b| b.half_width())
+            .fold(0.0, f64::max)
+    }
+
+    fn print_status(&self) {
+        // percentages are followed by the half-width of their 95% confidence interval
+        println!("Individual roads:\n[");
+        for (r, visits) in self.visits.0.iter().enumerate() {
+            println!(
+                " {0: <20} = {1: >15} -- {2: <15} ± {3:.4} ",
+                landing_name(r),
+                visits,
+                100.0 * (*visits as f32) / (self.turns as f32),
+                self.visitbatches[r].half_width(),
+            );
+
+            if r == Road::Jail as usize {
+                println!(
+                    " {0: <20} = {1: >15} -- {2: <15} ± {3:.4} ",
+                    landing_name(IN_JAIL),
+                    self.injail,
+                    100.0 * (self.injail as f32) / (self.turns as f32),
+                    self.visitbatches[IN_JAIL].half_width(),
+                );
             }
-            BoardGroup::Green => Road::RegentStreet.expenses() + Road::OxfordStreet.expenses(),
-            BoardGroup::DarkBlue => Road::ParkLane.expenses() + Road::Mayfair.expenses(),
-            BoardGroup::Utility => Road::IncomeTax.expenses() + Road::ElectricCompany.expenses(),
-            BoardGroup::Station => {
-                Road::KingsCrossStation.expenses()
-                    + Road::MaryleboneStation.expenses()
-                    + Road::FenchurchStStation.expenses()
-                    + Road::LiverpoolStStation.expenses()
+        }
+        println!("]\n");
+
+        println!("Board groups: [");
+        for (g, visits) in self.groups.iter().enumerate() {
+            let group = BoardGroup::from_usize(g).unwrap();
+            println!(
+                " {0: <20} = {1: >15} -- {2: <15} ± {3: <8.4} -- {4: <15} ",
+                group.as_str(),
+                visits,
+                100.0 * (*visits as f32) / (self.turns as f32),
+                self.groupbatches[g].half_width(),
+                games_won(self.groupwinner[g]),
+            );
+        }
+        println!("]\n");
+
+        // what each square and group earns over a game, net of buying and developing it
+        println!(
+            " {0: <20} | {1: >10} | {2: >10} | {3: >10} | {4: >10} | {5: >10} | {6: >8} | {7: >8} ",
+            "Profit per game", "mean", "sd", "10%", "median", "90%", "wins %", "±"
+        );
+        let rows = (0..40)
+            .filter(|r| Road::from_usize(*r).unwrap().price().is_some())
+            .map(|r| {
+                (
+                    landing_name(r),
+                    &self.profit[r],
+                    games_won(self.winner[r]),
+                    &self.winbatches[r],
+                )
+            })
+            .chain(
+                (0..15)
+                    .filter(|g| BoardGroup::from_usize(*g).unwrap().ownable())
+                    .map(|g| {
+                        let group = BoardGroup::from_usize(g).unwrap();
+                        (
+                            group.as_str(),
+                            &self.groupprofit[g],
+                            games_won(self.groupwinner[g]),
+                            &self.groupwinbatches[g],
+                        )
+                    }),
+            );
+        for (name, profit, wins, batches) in rows {
+            println!(
+                " {0: <20} | {1: >10.1} | {2: >10.1} | {3: >10} | {4: >10} | {5: >10} | {6: >8.2} | {7: >8.2} ",
+                name,
+                profit.mean(),
+                profit.sd(),
+                profit.quantile(0.1),
+                profit.quantile(0.5),
+                profit.quantile(0.9),
+                100.0 * wins / self.games as f64,
+                batches.half_width()
+            );
+        }
+        println!();
+
+        println!(
+            " {0: <20} | {1: >12} | {2: >12} | {3: >10} ",
+            "Cards drawn on", "collected", "paid", "net/turn"
+        );
+        for r in 0..40 {
+            let (income, expense) = (self.cardincome.0[r], self.cardexpense.0[r]);
+            if income > 0 || expense > 0 {
+                println!(
+                    " {0: <20} | {1: >12} | {2: >12} | {3: >10.4} ",
+                    landing_name(r),
+                    income,
+                    expense,
+                    (income as f64 - expense as f64) / self.turns as f64
+                );
             }
-            BoardGroup::CommunityChest => 0,
-            BoardGroup::Chance => 0,
-            BoardGroup::Tax => 0,
-            BoardGroup::Jail => 0,
-            BoardGroup::GoParking => 0,
         }
+        println!();
+
+        println!(
+            "Card cash: {} ({} per turn)",
+            self.cardcash,
+            self.cardcash as f64 / self.turns as f64
+        );
     }
 }
 
-struct Board(pub [u128; 40]);
-
-struct Player {
-    visits: Board,
-    groups: [u128; 15],
+// A single token going round the board, counting where it lands.
+struct Player<R: Rng> {
+    tally: Tally,
     gameincome: Board,
     groupincome: [u128; 15],
-    winner: Board,
-    groupwinner: [u128; 15],
     position: Road,
-    turns: u64,
-    rng: rand::rngs::ThreadRng,
+    // the number of failed attempts to roll out of jail, if we're in it
+    jail: Option<u8>,
+    jailstrategy: JailStrategy,
+    rng: R,
+    chance: Deck<CardAction>,
+    community_chest: Deck<CardAction>,
+    events: Vec<CardEvent>,
 }
 
-impl Player {
-    fn new() -> Player {
+impl<R: Rng> Player<R> {
+    fn new(jailstrategy: JailStrategy, mut rng: R) -> Player<R> {
+        let chance = Deck::new(cards::chance(), &mut rng);
+        let community_chest = Deck::new(cards::community_chest(), &mut rng);
         Player {
-            visits: Board([0; 40]),
-            groups: [0; 15],
+            tally: Tally::new(),
             gameincome: Board([0; 40]),
             groupincome: [0; 15],
-            winner: Board([0; 40]),
-            groupwinner: [0; 15],
             position: Road::Go,
-            turns: 0,
-            rng: thread_rng(),
+            jail: None,
+            jailstrategy,
+            rng,
+            chance,
+            community_chest,
+            events: Vec::new(),
+        }
+    }
+
+    fn take_turn(&mut self) {
+        self.events.clear();
+
+        // short stayers buy their way out before rolling, and then take a normal turn
+        if self.jail.is_some() && self.jailstrategy == JailStrategy::ShortStay {
+            self.jail = None;
+            self.tally.fines += 50;
+        }
+
+        if let Some(attempts) = self.jail {
+            // out on a double, or pay the fine after the third failed attempt. Either way we
+            // move by this roll, and don't roll again.
+            let roll = self.roll();
+            if roll.is_double() {
+                self.jail = None;
+                self.advance(roll);
+            } else if attempts == 2 {
+                self.jail = None;
+                self.tally.fines += 50;
+                self.advance(roll);
+            } else {
+                self.jail = Some(attempts + 1);
+            }
+        } else {
+            // a double earns another roll, but the third in a row goes straight to jail
+            for doubles in 0..3 {
+                let roll = self.roll();
+                let again = roll.is_double();
+
+                if again && doubles == 2 {
+                    self.go_to_jail();
+                    break;
+                }
+
+                // being sent to jail ends the turn, doubles or not
+                if self.advance(roll) || !again {
+                    break;
+                }
+            }
+        }
+
+        // increment the number of turns
+        self.tally.turns += 1;
+        self.tally_cards();
+    }
+
+    // Count the cash from this turn's cards against the squares they were drawn on. We own no
+    // buildings, and play alone.
+    fn tally_cards(&mut self) {
+        for event in self.events.drain(..) {
+            let cash = event.action.cash(0, 0, 0);
+            let square = event.square as usize;
+            if cash > 0 {
+                self.tally.cardincome.0[square] += cash as u128;
+            } else {
+                self.tally.cardexpense.0[square] += -cash as u128;
+            }
+            self.tally.cardcash += cash;
         }
     }
 
-    fn take_turn(&mut self) -> () {
-        // roll the dice
+    fn roll(&mut self) -> RollPair {
         let r1 = DieRoll::roll(&mut self.rng);
         let r2 = DieRoll::roll(&mut self.rng);
+        RollPair::new(r1, r2)
+    }
 
-        // move
-        self.position = self.position.transition(RollPair::new(r1, r2));
+    // Move by a roll, and deal with wherever we end up. Returns true if we were sent to jail.
+    fn advance(&mut self, roll: RollPair) -> bool {
+        self.position = self.position.transition(roll);
+        self.land(&roll);
+        if self.position == Road::GotoJail || self.resolve_cards(&roll) {
+            self.go_to_jail();
+            return true;
+        }
+        false
+    }
+
+    // Being sent to jail counts as a landing in jail, rather than a visit to the square.
+    fn go_to_jail(&mut self) {
+        self.position = Road::Jail;
+        self.jail = Some(0);
+        self.tally.injail += 1;
+        self.tally.groups[BoardGroup::Jail as usize] += 1;
+    }
 
+    // Draw from the deck we're stood on, if any. Cards may move us on again, in which case we
+    // land more than once this turn. Returns true if a card sent us to jail.
+    fn resolve_cards(&mut self, roll: &RollPair) -> bool {
+        loop {
+            let action = match BoardGroup::classify_road(self.position) {
+                BoardGroup::Chance => self.chance.draw(),
+                BoardGroup::CommunityChest => self.community_chest.draw(),
+                _ => return false,
+            };
+            self.events.push(CardEvent {
+                square: self.position,
+                action,
+            });
+            if action == CardAction::GoToJail {
+                return true;
+            }
+            match action.destination(self.position) {
+                Some(dest) => {
+                    self.position = dest;
+                    self.land(roll);
+                }
+                None => return false,
+            }
+        }
+    }
+
+    // Count a landing, and the rent it would have paid a full owner given the roll that brought
+    // us here.
+    fn land(&mut self, roll: &RollPair) {
         // update the local counters
-        self.visits.0[self.position as usize] += 1;
+        self.tally.visits.0[self.position as usize] += 1;
         // add to the group total
-        self.groups[BoardGroup::classify_road(self.position) as usize] += 1;
+        self.tally.groups[BoardGroup::classify_road(self.position) as usize] += 1;
 
         // Add to the income total
-        self.gameincome.0[self.position as usize] += self.position.rent().max();
+        let rent = self.position.rent().charge(Holding::full(), roll);
+        self.gameincome.0[self.position as usize] += rent;
+        self.groupincome[BoardGroup::classify_road(self.position) as usize] += rent;
+    }
 
-        self.groupincome[BoardGroup::classify_road(self.position) as usize] +=
-            self.position.rent().max();
+    // Back to Go, with freshly shuffled decks.
+    fn new_game(&mut self) {
+        self.position = Road::Go;
+        self.jail = None;
+        self.chance = Deck::new(cards::chance(), &mut self.rng);
+        self.community_chest = Deck::new(cards::community_chest(), &mut self.rng);
+    }
 
-        // increment the number of turns
-        self.turns += 1;
+    fn tally_game(&mut self) {
+        self.tally.games += 1;
+        for (total, inc) in self.tally.income.0.iter_mut().zip(self.gameincome.0.iter()) {
+            *total += inc;
+        }
+        for (total, inc) in self
+            .tally
+            .groupincome
+            .iter_mut()
+            .zip(self.groupincome.iter())
+        {
+            *total += inc;
+        }
+
+        // profit is income less what it cost to buy and fully develop, and can well be negative
+        let profits: Vec<i64> = (0..40)
+            .map(|r| self.gameincome.0[r] as i64 - Road::from_usize(r).unwrap().expenses() as i64)
+            .collect();
+        let groupprofits: Vec<i64> = (0..15)
+            .map(|g| {
+                self.groupincome[g] as i64 - BoardGroup::from_usize(g).unwrap().expenses() as i64
+            })
+            .collect();
+        for (d, p) in self.tally.profit.iter_mut().zip(profits.iter()) {
+            d.add(*p);
+        }
+        for (d, p) in self.tally.groupprofit.iter_mut().zip(groupprofits.iter()) {
+            d.add(*p);
+        }
+
+        // only squares and groups that can be bought can win
+        let roads: Vec<usize> = (0..40)
+            .filter(|r| Road::from_usize(*r).unwrap().price().is_some())
+            .collect();
+        let groups: Vec<usize> = (0..15)
+            .filter(|g| BoardGroup::from_usize(*g).unwrap().ownable())
+            .collect();
+        share_win(&mut self.tally.winner, &roads, &profits);
+        share_win(&mut self.tally.groupwinner, &groups, &groupprofits);
+
+        self.gameincome = Board([0; 40]);
+        self.groupincome = [0; 15];
     }
+}
+
+// A game's win, in parts that divide evenly between any number of the forty squares.
+const WIN_SHARE: u128 = lcm_to(40);
 
-    fn take_turns(&mut self, turns: u128) -> () {
-        for _ in 0..turns {
-            self.take_turn();
+const fn lcm_to(n: u128) -> u128 {
+    let mut lcm = 1;
+    let mut i = 2;
+    while i <= n {
+        let (mut a, mut b) = (lcm, i);
+        while b != 0 {
+            (a, b) = (b, a % b);
         }
+        lcm = lcm / a * i;
+        i += 1;
     }
+    lcm
+}
+
+fn games_won(shares: u128) -> f64 {
+    shares as f64 / WIN_SHARE as f64
+}
+
+// Credit a game's win to whichever candidates made the most profit, split evenly between any that
+// tie. Nobody wins on a board with nothing to buy.
+fn share_win(winner: &mut [u128], candidates: &[usize], profits: &[i64]) {
+    let best = match candidates.iter().map(|i| profits[*i]).max() {
+        Some(best) => best,
+        None => return,
+    };
+    let leaders: Vec<&usize> = candidates.iter().filter(|i| profits[**i] == best).collect();
+    for i in leaders.iter() {
+        winner[**i] += WIN_SHARE / leaders.len() as u128;
+    }
+}
+
+// Play the same number of games under each jail strategy, and show where they differ.
+fn compare_jail_strategies(options: &Options) {
+    let short = run(options, JailStrategy::ShortStay);
+    let long = run(options, JailStrategy::LongStay);
+
+    // visits as a percentage of turns, and income per game
+    let pct = |visits: u128, t: &Tally| 100.0 * visits as f64 / t.turns as f64;
+    let per_game = |income: u128, t: &Tally| income as f64 / t.games as f64;
+
+    // the two runs only play different numbers of games if they were stopped early
+    let (sg, lg) = (
+        short.games / options.players as u64,
+        long.games / options.players as u64,
+    );
+    let games = if sg == lg {
+        sg.to_string()
+    } else {
+        format!("{} and {}", sg, lg)
+    };
+    println!(
+        "Jail strategies over {} games of {} turns on the {} board: {} vs {} (seed {})\n",
+        games,
+        options.turns,
+        edition().name,
+        JailStrategy::ShortStay.as_str(),
+        JailStrategy::LongStay.as_str(),
+        options.seed
+    );
 
-    fn tally_game(&mut self) -> () {
-        let mut bestix: usize = 0;
-        let mut best = 0;
-        for (i, inc) in self.gameincome.0.iter().enumerate() {
-            if *inc - Road::from_usize(i).unwrap().expenses() > best {
-                best = *inc - Road::from_usize(i).unwrap().expenses();
-                bestix = i;
+    println!(
+        " {0: <20} | {1: >10} {2: >10} {3: >10} | {4: >12} {5: >12} {6: >12} ",
+        "Road", "short %", "long %", "diff", "short inc", "long inc", "diff"
+    );
+    for r in 0..40 {
+        let (sv, lv) = (pct(short.visits.0[r], &short), pct(long.visits.0[r], &long));
+        let (si, li) = (
+            per_game(short.income.0[r], &short),
+            per_game(long.income.0[r], &long),
+        );
+        println!(
+            " {0: <20} | {1: >10.4} {2: >10.4} {3: >+10.4} | {4: >12.2} {5: >12.2} {6: >+12.2} ",
+            landing_name(r),
+            sv,
+            lv,
+            sv - lv,
+            si,
+            li,
+            si - li
+        );
+    }
+    let (sv, lv) = (pct(short.injail, &short), pct(long.injail, &long));
+    println!(
+        " {0: <20} | {1: >10.4} {2: >10.4} {3: >+10.4} |",
+        "InJail",
+        sv,
+        lv,
+        sv - lv
+    );
+    println!();
+
+    println!(
+        " {0: <20} | {1: >10} {2: >10} {3: >10} | {4: >12} {5: >12} {6: >12} ",
+        "Group", "short %", "long %", "diff", "short inc", "long inc", "diff"
+    );
+    for g in 0..15 {
+        let group = BoardGroup::from_usize(g).unwrap();
+        let (sv, lv) = (pct(short.groups[g], &short), pct(long.groups[g], &long));
+        let (si, li) = (
+            per_game(short.groupincome[g], &short),
+            per_game(long.groupincome[g], &long),
+        );
+        println!(
+            " {0: <20} | {1: >10.4} {2: >10.4} {3: >+10.4} | {4: >12.2} {5: >12.2} {6: >+12.2} ",
+            group.as_str(),
+            sv,
+            lv,
+            sv - lv,
+            si,
+            li,
+            si - li
+        );
+    }
+    println!();
+
+    println!(
+        "Fines paid per game: {:.2} vs {:.2}",
+        per_game(short.fines, &short),
+        per_game(long.fines, &long)
+    );
+}
+
+// How a slot in a landings table is labelled, telling apart jail and just visiting.
+fn landing_name(r: usize) -> &'static str {
+    if r == IN_JAIL {
+        return "InJail";
+    }
+    match Road::from_usize(r).unwrap() {
+        Road::Jail => "JustVisiting",
+        road => road.as_str(),
+    }
+}
+
+// Work out landing frequencies exactly, both in the long run and over games as long as the ones
+// simulated, and check the latter against simulated games. The exact chain treats every card as
+// drawn from the whole deck, where a game deals its freshly shuffled decks in order, so a small
+// real difference can remain once the errors get tight, mostly in being sent to jail.
+fn solve(options: &Options) {
+    let chain = Chain::new(options.jailstrategy);
+    let stationary = chain.landings(&chain.stationary());
+    let mut exact = chain.horizon(options.turns);
+    for l in exact.iter_mut() {
+        *l /= options.turns as f64;
+    }
+
+    let total = run(options, options.jailstrategy);
+    let sampled = |visits: u128| 100.0 * visits as f64 / total.turns as f64;
+
+    println!(
+        "Landing frequencies on the {} board ({}), exactly in the long run and over games of {} turns, against {} simulated games (seed {})\n",
+        edition().name,
+        options.jailstrategy.as_str(),
+        options.turns,
+        total.games / options.players as u64,
+        options.seed
+    );
+
+    // sampled figures are followed by the half-width of their 95% confidence interval, and the
+    // difference is from the exact figure over games of the same length
+    println!(
+        " {0: <20} | {1: >10} {2: >10} {3: >10} {4: >8} {5: >10} ",
+        "Road", "long run %", "game %", "sampled %", "±", "diff"
+    );
+    for (r, landings) in exact.iter().enumerate() {
+        let (name, visits) = if r == IN_JAIL {
+            ("InJail", total.injail)
+        } else {
+            (landing_name(r), total.visits.0[r])
+        };
+        println!(
+            " {0: <20} | {1: >10.4} {2: >10.4} {3: >10.4} {4: >8.4} {5: >+10.4} ",
+            name,
+            100.0 * stationary[r],
+            100.0 * landings,
+            sampled(visits),
+            total.visitbatches[r].half_width(),
+            100.0 * landings - sampled(visits)
+        );
+    }
+    println!();
+
+    println!(
+        " {0: <20} | {1: >10} {2: >10} {3: >10} {4: >8} {5: >10} ",
+        "Group", "long run %", "game %", "sampled %", "±", "diff"
+    );
+    let groups = group_landings(&exact);
+    for (g, landings) in group_landings(&stationary).iter().enumerate() {
+        let group = BoardGroup::from_usize(g).unwrap();
+        println!(
+            " {0: <20} | {1: >10.4} {2: >10.4} {3: >10.4} {4: >8.4} {5: >+10.4} ",
+            group.as_str(),
+            100.0 * landings,
+            100.0 * groups[g],
+            sampled(total.groups[g]),
+            total.groupbatches[g].half_width(),
+            100.0 * groups[g] - sampled(total.groups[g])
+        );
+    }
+}
+
+// Work out exactly how often each square is landed on over the first turns of a game.
+fn horizon(jailstrategy: JailStrategy, turns: u64) {
+    let exact = Chain::new(jailstrategy).horizon(turns);
+
+    println!(
+        "Expected landings in the first {} turns from Go on the {} board ({})\n",
+        turns,
+        edition().name,
+        jailstrategy.as_str()
+    );
+
+    println!(" {0: <20} | {1: >10} ", "Road", "landings");
+    for (r, landings) in exact.iter().enumerate() {
+        println!(" {0: <20} | {1: >10.4} ", landing_name(r), landings);
+    }
+    println!();
+
+    println!(" {0: <20} | {1: >10} ", "Group", "landings");
+    for (g, landings) in group_landings(&exact).iter().enumerate() {
+        let group = BoardGroup::from_usize(g).unwrap();
+        println!(" {0: <20} | {1: >10.4} ", group.as_str(), landings);
+    }
+    println!();
+
+    // from no houses to a hotel on the streets, one to four stations, and one or both utilities
+    println!("Expected rent from each square over the same turns, by the houses on a street, or");
+    println!("the stations or utilities its owner holds\n");
+    println!(
+        " {0: <20} | {1: >9} | {2: >9} | {3: >9} | {4: >9} | {5: >9} | {6: >9} ",
+        "Road", "none/1", "1/2", "2/3", "3/4", "4", "hotel"
+    );
+    for (r, landings) in exact.iter().enumerate().take(40) {
+        let levels = Road::from_usize(r).unwrap().rent().levels();
+        if levels.is_empty() {
+            continue;
+        }
+        let mut line = format!(" {0: <20} ", landing_name(r));
+        for level in 0..6 {
+            match levels.get(level) {
+                Some(rent) => line += &format!("| {0: >9.2} ", landings * *rent as f64),
+                None => line += &format!("| {0: >9} ", "-"),
             }
         }
+        println!("{}", line);
+    }
+}
 
-        self.winner.0[bestix] += 1;
+// Play one batch of games, with players seeded from the batch's index.
+fn play_batch(index: u64, games: u64, options: &Options, jailstrategy: JailStrategy) -> Tally {
+    let count = options.players as u64;
+    let mut players: Vec<Player<StdRng>> = (0..count)
+        .map(|i| Player::new(jailstrategy, stream(options.seed, index * count + i)))
+        .collect();
 
-        let mut bestgroupix: usize = 0;
-        let mut bestgroup = 0;
-        for (g, inc) in self.groupincome.iter().enumerate() {
-            if *inc - BoardGroup::from_usize(g).unwrap().expenses() > bestgroup {
-                bestgroup = *inc - BoardGroup::from_usize(g).unwrap().expenses();
-                bestgroupix = g;
+    for _ in 0..games {
+        // reset the players to the start of a game, and take turns about
+        for p in players.iter_mut() {
+            p.new_game();
+        }
+        for _ in 0..options.turns {
+            for p in players.iter_mut() {
+                p.take_turn();
             }
         }
+        for p in players.iter_mut() {
+            p.tally_game();
+        }
+    }
 
-        self.groupwinner[bestgroupix] += 1;
+    let mut tally = Tally::new();
+    for p in players.iter() {
+        tally.merge(&p.tally);
+    }
+    tally
+}
 
-        self.gameincome = Board([0; 40]);
-        self.groupincome = [0; 15];
+// Batches to play before trusting the spread between them enough to stop on a tolerance.
+const MIN_BATCHES: u64 = 10;
+
+// Play games in batches spread across worker threads, reporting progress as batches come in.
+// Every batch has its own players seeded from its index, and batches are added to the total in
+// index order whatever order they finish in, so the totals depend on the seed and batch size but
+// not on how many threads shared the work. That stops being true when a tolerance or time limit
+// ends the run early, as how many batches had been started by then depends on timing.
+fn run(options: &Options, jailstrategy: JailStrategy) -> Tally {
+    let batches = options.games.div_ceil(options.batch);
+    let next = AtomicU64::new(0);
+    let stop = AtomicBool::new(false);
+    let (tx, rx) = mpsc::channel();
+    let mut total = Tally::new();
+    // batches that finished ahead of one before them, waiting for their turn to be added
+    let mut pending = BTreeMap::new();
+    let mut merged = 0;
+    let start = Instant::now();
+    let mut reason = None;
+
+    thread::scope(|s| {
+        for _ in 0..options.threads {
+            let tx = tx.clone();
+            let next = &next;
+            let stop = &stop;
+            s.spawn(move || loop {
+                // every batch taken is played and sent, so that none are missing from the order
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= batches {
+                    break;
+                }
+                let games = options.batch.min(options.games - index * options.batch);
+                let mut tally = play_batch(index, games, options, jailstrategy);
+                // a short last batch would count for as much as a full one in the batch means
+                if games == options.batch {
+                    tally.end_batch();
+                }
+                if tx.send((index, tally)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        // batches already being played when we stop are still counted
+        for (index, tally) in rx {
+            pending.insert(index, tally);
+            while let Some(tally) = pending.remove(&merged) {
+                total.merge(&tally);
+                merged += 1;
+            }
+            let worst = total.worst_half_width();
+            eprint!(
+                "\rGame: {} - {:.1}%, worst ± {:.4}",
+                total.games / options.players as u64,
+                100.0 * total.games as f64 / (options.games * options.players as u64) as f64,
+                worst
+            );
+
+            if reason.is_some() {
+                continue;
+            }
+            let played = total.visitbatches[0].count;
+            if options
+                .tolerance
+                .is_some_and(|t| played >= MIN_BATCHES && worst < t)
+            {
+                reason = Some("every interval is within the tolerance");
+            } else if options
+                .time
+                .is_some_and(|t| start.elapsed() >= Duration::from_secs(t))
+            {
+                reason = Some("out of time");
+            }
+            if reason.is_some() {
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+    });
+    eprintln!();
+    match reason {
+        Some(reason) => eprintln!(
+            "Stopped after {} games: {}",
+            total.games / options.players as u64,
+            reason
+        ),
+        None if options.tolerance.is_some() => {
+            eprintln!("Played every game without getting within the tolerance")
+        }
+        None => {}
     }
 
-    fn print_status(&self) -> () {
-        println!("Individual roads:\n[");
-        for (r, visits) in self.visits.0.iter().enumerate() {
-            let road = Road::from_usize(r).unwrap();
+    total
+}
 
-            print!(
-                " {0: <20} = {1: >15} -- {2: <15} ",
-                road.as_str(),
-                visits,
-                100.0 * (*visits as f32) / (self.turns as f32),
+// Play all the games, and print the combined counts at the end.
+fn simulate(options: &Options) {
+    let total = run(options, options.jailstrategy);
+
+    let out = match options.format {
+        Format::Json => export::json(&total, options),
+        Format::Csv => export::csv(&total, options),
+        Format::Text => {
+            println!(
+                "{} games of {} turns on the {} board, {} player(s), {} (seed {})\n",
+                total.games / options.players as u64,
+                options.turns,
+                edition().name,
+                options.players,
+                options.jailstrategy.as_str(),
+                options.seed
             );
+            total.print_status();
+            return;
+        }
+    };
 
-            if road == self.position {
-                println!(" <<====== ");
-            } else {
-                println!("");
+    match &options.output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, out) {
+                eprintln!("error: couldn't write {}: {}", path, e);
+                std::process::exit(1);
             }
         }
-        println!("]\n");
+        None => print!("{}", out),
+    }
+}
 
-        println!("Board groups: [");
-        for (g, visits) in self.groups.iter().enumerate() {
-            let group = BoardGroup::from_usize(g).unwrap();
+// Play full games against each other, and report who wins and where the rent comes from.
+fn play(options: &Options) {
+    // strategies are dealt out to the seats in turn
+    let seats: Vec<&String> = options
+        .strategies
+        .iter()
+        .cycle()
+        .take(options.players)
+        .collect();
+    let names: Vec<String> = seats
+        .iter()
+        .map(|name| strategy::named(name, options.jailstrategy).unwrap().name())
+        .collect();
+
+    let mut wins = vec![0u64; options.players];
+    let mut rounds = 0;
+    let mut bankruptcies = 0;
+    let mut finished = 0;
+    let mut rent = [0u128; 40];
+    // how many times each square went to auction, and the total it went for
+    let mut auctioned = [0u64; 40];
+    let mut auctionprice = [0i64; 40];
+    // games where the bank ran out of houses or hotels for someone, and who won those
+    let mut short = 0;
+    let mut shortwins = vec![0u64; options.players];
+    let mut shortages = vec![0u64; options.players];
+    let mut houseauctions = 0;
+    // where players went bankrupt, and who to
+    let mut busts = [0u64; 40];
+    let mut bust = vec![0u64; options.players];
+    let mut busted = vec![0u64; options.players];
+    let mut tobank = 0;
+
+    for g in 0..options.games {
+        let strategies = seats
+            .iter()
+            .map(|name| strategy::named(name, options.jailstrategy).unwrap())
+            .collect();
+        let result =
+            Game::new(strategies, options.auction, stream(options.seed, g)).play(options.turns);
+        wins[result.winner] += 1;
+        rounds += result.rounds;
+        bankruptcies += result.bankruptcies.len() as u64;
+        if result.bankruptcies.len() == options.players - 1 {
+            finished += 1;
+        }
+        for b in result.bankruptcies.iter() {
+            busts[b.square as usize] += 1;
+            bust[b.player] += 1;
+            match b.creditor {
+                Creditor::Player(c) => busted[c] += 1,
+                Creditor::Bank => tobank += 1,
+            }
+        }
+        for (total, r) in rent.iter_mut().zip(result.rent.iter()) {
+            *total += r;
+        }
+        for (road, price) in result.auctions.iter() {
+            auctioned[*road as usize] += 1;
+            auctionprice[*road as usize] += price;
+        }
+        if result.shortages.iter().any(|n| *n > 0) {
+            short += 1;
+            shortwins[result.winner] += 1;
+        }
+        for (total, n) in shortages.iter_mut().zip(result.shortages.iter()) {
+            *total += n;
+        }
+        houseauctions += result.house_auctions;
+    }
+
+    let games = options.games as f64;
+    println!(
+        "{} games between {} players on the {} board, at most {} rounds each, {}, {} (seed {})\n",
+        options.games,
+        options.players,
+        edition().name,
+        options.turns,
+        options.jailstrategy.as_str(),
+        options.auction.as_str(),
+        options.seed
+    );
+    println!(
+        "Played to the end: {:.2}%, rounds per game: {:.1}, bankruptcies per game: {:.2}\n",
+        100.0 * finished as f64 / games,
+        rounds as f64 / games,
+        bankruptcies as f64 / games
+    );
+    println!(
+        "Bankruptcies to the bank: {:.2}%\n",
+        100.0 * tobank as f64 / bankruptcies.max(1) as f64
+    );
+    println!(
+        "Bank out of buildings: {:.2}% of games, house auctions per game: {:.4}\n",
+        100.0 * short as f64 / games,
+        houseauctions as f64 / games
+    );
+
+    // how often each player goes bankrupt, and how many others they bankrupt a game; and who
+    // gets shut out when the houses run out, and who wins the games where they do
+    println!(
+        " {0: <6} {1: <20} | {2: >10} | {3: >10} | {4: >10} | {5: >10} | {6: >10} ",
+        "Seat", "Strategy", "wins %", "bust %", "busted", "shut out", "short wins"
+    );
+    for (p, w) in wins.iter().enumerate() {
+        println!(
+            " {0: <6} {1: <20} | {2: >10.2} | {3: >10.2} | {4: >10.2} | {5: >10.2} | {6: >10.2} ",
+            p + 1,
+            names[p],
+            100.0 * *w as f64 / games,
+            100.0 * bust[p] as f64 / games,
+            busted[p] as f64 / games,
+            shortages[p] as f64 / games,
+            100.0 * shortwins[p] as f64 / short.max(1) as f64
+        );
+    }
+    println!();
+
+    // what a square fetches at auction is a market value to set against its list price
+    println!(
+        " {0: <20} | {1: >10} | {2: >10} | {3: >10} | {4: >10} ",
+        "Road", "rent/game", "price", "auctions %", "auction"
+    );
+    for (r, total) in rent.iter().enumerate() {
+        if let Some(price) = Road::from_usize(r).unwrap().price() {
+            let value = if auctioned[r] > 0 {
+                format!("{:.2}", auctionprice[r] as f64 / auctioned[r] as f64)
+            } else {
+                String::from("-")
+            };
             println!(
-                " {0: <20} = {1: >15} -- {2: <15} -- {3: <15} ",
-                group.as_str(),
-                visits,
-                100.0 * (*visits as f32) / (self.turns as f32),
-                self.groupwinner[g],
+                " {0: <20} | {1: >10.2} | {2: >10} | {3: >10.2} | {4: >10} ",
+                landing_name(r),
+                *total as f64 / games,
+                price,
+                100.0 * auctioned[r] as f64 / games,
+                value
+            );
+        }
+    }
+    println!();
+
+    println!(" {0: <20} | {1: >12} ", "Bankrupted on", "bankruptcy %");
+    for (r, n) in busts.iter().enumerate() {
+        if *n > 0 {
+            println!(
+                " {0: <20} | {1: >12.2} ",
+                landing_name(r),
+                100.0 * *n as f64 / bankruptcies as f64
             );
         }
-        println!("]");
     }
 }
 
 fn main() {
-    let mut p = Player::new();
-    p.visits.0[0] = 1;
-    let total_games = 1000000000;
-    for i in 0..total_games {
-        // run 1000 games
-        for _ in 0..1000 {
-            // reset the player to the start of a game
-            p.position = Road::Go;
-            p.take_turns(50);
-            p.tally_game();
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
         }
-        // clear the screen
-        print!("{}[2J", 27 as char);
-        // print status so far
-        println!("Game: {} - {}", i, 100.0 * i as f32 / total_games as f32);
-        p.print_status();
+    };
+
+    let board = match &options.board {
+        Some(path) => Edition::load(path),
+        None => Edition::builtin(options.edition.as_deref().unwrap_or("uk")),
+    };
+    match board {
+        Ok(board) => edition::select(board),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }
+    }
+
+    match options.command {
+        Command::Simulate => simulate(&options),
+        Command::Solve => solve(&options),
+        Command::Report => horizon(options.jailstrategy, options.turns),
+        Command::CompareJail => compare_jail_strategies(&options),
+        Command::Play => play(&options),
+        Command::Help => print!("{}", cli::USAGE),
     }
 }
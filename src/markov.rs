@@ -0,0 +1,255 @@
+use num_traits::FromPrimitive;
+
+use crate::cards::{self, CardAction};
+use crate::{BoardGroup, DieRoll, JailStrategy, Road, RollPair};
+
+// Landings are counted per square, plus one slot for being sent to jail (as opposed to just
+// visiting it).
+pub const IN_JAIL: usize = 40;
+pub const LANDINGS: usize = 41;
+
+// A turn starts either free on one of the squares, or in jail having failed to roll out of it
+// zero, one or two times.
+const STATES: usize = 43;
+
+fn jailed(attempts: u8) -> usize {
+    40 + attempts as usize
+}
+
+// Every throw of two dice, each equally likely.
+fn rolls() -> Vec<RollPair> {
+    let mut rolls = Vec::with_capacity(36);
+    for a in 1..7 {
+        for b in 1..7 {
+            rolls.push(RollPair::new(
+                DieRoll::from_u8(a).unwrap(),
+                DieRoll::from_u8(b).unwrap(),
+            ));
+        }
+    }
+    rolls
+}
+
+// The cards that might be drawn on a square, each equally likely.
+fn deck(pos: Road) -> Option<Vec<CardAction>> {
+    match BoardGroup::classify_road(pos) {
+        BoardGroup::Chance => Some(cards::chance()),
+        BoardGroup::CommunityChest => Some(cards::community_chest()),
+        _ => None,
+    }
+}
+
+// Everything a single turn from one state can lead to, weighted by probability. This mirrors
+// Player::take_turn, but follows every branch instead of sampling one.
+struct TurnOutcome {
+    // expected number of landings on each square during the turn
+    landings: [f64; LANDINGS],
+    // probability of starting the next turn in each state
+    next: [f64; STATES],
+}
+
+impl TurnOutcome {
+    fn new(state: usize, jailstrategy: JailStrategy) -> TurnOutcome {
+        let mut t = TurnOutcome {
+            landings: [0.0; LANDINGS],
+            next: [0.0; STATES],
+        };
+
+        if state < 40 {
+            t.roll(Road::from_usize(state).unwrap(), 0, 1.0);
+            return t;
+        }
+
+        let attempts = (state - 40) as u8;
+        match jailstrategy {
+            JailStrategy::ShortStay => t.roll(Road::Jail, 0, 1.0),
+            JailStrategy::LongStay => {
+                for roll in rolls() {
+                    let p = 1.0 / 36.0;
+                    if roll.is_double() || attempts == 2 {
+                        t.arrive(Road::Jail.transition(roll), p, None);
+                    } else {
+                        t.next[jailed(attempts + 1)] += p;
+                    }
+                }
+            }
+        }
+        t
+    }
+
+    // Roll from a square, having already thrown some doubles this turn.
+    fn roll(&mut self, pos: Road, doubles: u8, p: f64) {
+        for roll in rolls() {
+            let p = p / 36.0;
+            if !roll.is_double() {
+                self.arrive(pos.transition(roll), p, None);
+            } else if doubles == 2 {
+                self.jail(p);
+            } else {
+                self.arrive(pos.transition(roll), p, Some(doubles + 1));
+            }
+        }
+    }
+
+    // Land on a square and resolve any cards. If we threw a double to get here, `again` holds
+    // how many we've thrown so far.
+    fn arrive(&mut self, pos: Road, p: f64, again: Option<u8>) {
+        self.landings[pos as usize] += p;
+
+        if pos == Road::GotoJail {
+            self.jail(p);
+            return;
+        }
+
+        let actions = match deck(pos) {
+            Some(actions) => actions,
+            None => {
+                self.settle(pos, p, again);
+                return;
+            }
+        };
+        let p = p / actions.len() as f64;
+        for action in actions {
+            if action == CardAction::GoToJail {
+                self.jail(p);
+                continue;
+            }
+            match action.destination(pos) {
+                Some(dest) => self.arrive(dest, p, again),
+                None => self.settle(pos, p, again),
+            }
+        }
+    }
+
+    // Either roll again, or finish the turn where we are.
+    fn settle(&mut self, pos: Road, p: f64, again: Option<u8>) {
+        match again {
+            Some(doubles) => self.roll(pos, doubles, p),
+            None => self.next[pos as usize] += p,
+        }
+    }
+
+    fn jail(&mut self, p: f64) {
+        self.landings[IN_JAIL] += p;
+        self.next[jailed(0)] += p;
+    }
+}
+
+// The board as a Markov chain over the state a turn starts in.
+pub struct Chain {
+    turns: Vec<TurnOutcome>,
+}
+
+impl Chain {
+    pub fn new(jailstrategy: JailStrategy) -> Chain {
+        Chain {
+            turns: (0..STATES)
+                .map(|s| TurnOutcome::new(s, jailstrategy))
+                .collect(),
+        }
+    }
+
+    // Advance a distribution over states by one turn.
+    fn step(&self, dist: &[f64]) -> Vec<f64> {
+        let mut next = vec![0.0; STATES];
+        for (p, turn) in dist.iter().zip(self.turns.iter()) {
+            for (n, q) in next.iter_mut().zip(turn.next.iter()) {
+                *n += p * q;
+            }
+        }
+        next
+    }
+
+    // The distribution over states that every game starts in.
+    fn start() -> Vec<f64> {
+        let mut dist = vec![0.0; STATES];
+        dist[Road::Go as usize] = 1.0;
+        dist
+    }
+
+    // The long run distribution over states, found by power iteration from Go.
+    pub fn stationary(&self) -> Vec<f64> {
+        let mut dist = Chain::start();
+        for _ in 0..100000 {
+            let next = self.step(&dist);
+            let delta: f64 = next
+                .iter()
+                .zip(dist.iter())
+                .map(|(a, b)| (a - b).abs())
+                .sum();
+            dist = next;
+            if delta < 1e-15 {
+                break;
+            }
+        }
+        dist
+    }
+
+    // Expected landings on each square during a turn started from the given distribution.
+    pub fn landings(&self, dist: &[f64]) -> [f64; LANDINGS] {
+        let mut landings = [0.0; LANDINGS];
+        for (p, turn) in dist.iter().zip(self.turns.iter()) {
+            for (l, q) in landings.iter_mut().zip(turn.landings.iter()) {
+                *l += p * q;
+            }
+        }
+        landings
+    }
+
+    // Expected landings on each square over the first few turns of a game, starting from Go.
+    pub fn horizon(&self, turns: u64) -> [f64; LANDINGS] {
+        let mut total = [0.0; LANDINGS];
+        let mut dist = Chain::start();
+        for _ in 0..turns {
+            for (t, l) in total.iter_mut().zip(self.landings(&dist).iter()) {
+                *t += l;
+            }
+            dist = self.step(&dist);
+        }
+        total
+    }
+}
+
+// Sum landings by board group, counting being sent to jail towards the jail group.
+pub fn group_landings(landings: &[f64; LANDINGS]) -> [f64; 15] {
+    let mut groups = [0.0; 15];
+    for (r, l) in landings.iter().enumerate() {
+        let group = if r == IN_JAIL {
+            BoardGroup::Jail
+        } else {
+            BoardGroup::classify_road(Road::from_usize(r).unwrap())
+        };
+        groups[group as usize] += l;
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_turn_ends_somewhere() {
+        for jailstrategy in [JailStrategy::ShortStay, JailStrategy::LongStay] {
+            for state in 0..STATES {
+                let total: f64 = TurnOutcome::new(state, jailstrategy).next.iter().sum();
+                assert!(
+                    (total - 1.0).abs() < 1e-12,
+                    "from state {} with {:?}, the next turn starts {} of the time",
+                    state,
+                    jailstrategy,
+                    total
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn the_long_run_is_a_distribution() {
+        for jailstrategy in [JailStrategy::ShortStay, JailStrategy::LongStay] {
+            // rounding builds up a little over the many steps it takes to settle
+            let total: f64 = Chain::new(jailstrategy).stationary().iter().sum();
+            assert!((total - 1.0).abs() < 1e-9, "{}", total);
+        }
+    }
+}
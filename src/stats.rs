@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+// Histogram buckets are this wide, in pounds.
+const BUCKET: i64 = 50;
+
+// How one quantity was spread over many games: its moments, its extremes, and a histogram, so
+// that distributions from separate batches can be merged without keeping every game.
+#[derive(Clone)]
+pub struct Distribution {
+    pub count: u64,
+    sum: i128,
+    squares: i128,
+    pub min: i64,
+    pub max: i64,
+    // games in each bucket, keyed by the bucket's lower bound divided by BUCKET
+    buckets: BTreeMap<i64, u64>,
+}
+
+impl Distribution {
+    pub fn new() -> Distribution {
+        Distribution {
+            count: 0,
+            sum: 0,
+            squares: 0,
+            min: i64::MAX,
+            max: i64::MIN,
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, x: i64) {
+        self.count += 1;
+        self.sum += x as i128;
+        self.squares += (x as i128) * (x as i128);
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+        *self.buckets.entry(x.div_euclid(BUCKET)).or_insert(0) += 1;
+    }
+
+    pub fn merge(&mut self, other: &Distribution) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.squares += other.squares;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        for (bucket, n) in other.buckets.iter() {
+            *self.buckets.entry(*bucket).or_insert(0) += n;
+        }
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum as f64 / self.count as f64
+    }
+
+    // The sample standard deviation.
+    pub fn sd(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        let n = self.count as f64;
+        let mean = self.mean();
+        ((self.squares as f64 - n * mean * mean) / (n - 1.0))
+            .max(0.0)
+            .sqrt()
+    }
+
+    // The value below which a fraction q of games fell, to within a bucket: the middle of the
+    // bucket it falls in, kept within the extremes actually seen.
+    pub fn quantile(&self, q: f64) -> i64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let target = (q * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, n) in self.buckets.iter() {
+            seen += n;
+            if seen >= target {
+                return (bucket * BUCKET + BUCKET / 2).clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+}
+
+// Multiplies a standard error into the half-width of a 95% confidence interval.
+const Z95: f64 = 1.96;
+
+// The value of one quantity in each batch of games, kept as moments. Batches are long enough to
+// be close to independent of each other, so the spread of their values gives a standard error
+// for the overall figure even where the games within a batch are not independent.
+#[derive(Clone, Copy)]
+pub struct Batches {
+    pub count: u64,
+    sum: f64,
+    squares: f64,
+}
+
+impl Batches {
+    pub fn new() -> Batches {
+        Batches {
+            count: 0,
+            sum: 0.0,
+            squares: 0.0,
+        }
+    }
+
+    pub fn add(&mut self, x: f64) {
+        self.count += 1;
+        self.sum += x;
+        self.squares += x * x;
+    }
+
+    pub fn merge(&mut self, other: &Batches) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.squares += other.squares;
+    }
+
+    // The standard error of the mean over batches, or infinite until there are two batches to
+    // compare.
+    pub fn se(&self) -> f64 {
+        if self.count < 2 {
+            return f64::INFINITY;
+        }
+        let n = self.count as f64;
+        let mean = self.sum / n;
+        let variance = ((self.squares - n * mean * mean) / (n - 1.0)).max(0.0);
+        (variance / n).sqrt()
+    }
+
+    pub fn half_width(&self) -> f64 {
+        Z95 * self.se()
+    }
+}
@@ -0,0 +1,292 @@
+use crate::game::Auction;
+use crate::{BoardGroup, JailStrategy, Road};
+
+// What a player can see of the game when making a decision.
+pub struct View<'a> {
+    pub cash: i64,
+    // how developed each square is, from no houses up to 5 for a hotel
+    pub houses: &'a [u8; 40],
+}
+
+// The decisions a player makes during a game. The engine enforces the rules, so a strategy only
+// ever chooses between legal moves, each given with what it costs or raises. Most strategies differ
+// only in what they buy, bid on and build, so the other decisions have defaults.
+pub trait Strategy {
+    fn name(&self) -> String;
+
+    // How we'd rather get out of jail, where nothing else decides it.
+    fn jail(&self) -> JailStrategy;
+
+    // Buy an unowned square we've landed on, and can afford?
+    fn buy(&self, view: &View, road: Road) -> bool;
+
+    // The most we'd pay for a square at an English auction, or what we write down at a sealed
+    // one. Nothing means we stay out.
+    fn bid(&self, view: &View, road: Road, auction: Auction) -> i64;
+
+    // The most we'd pay for one of the bank's last houses, to put on this square, when it's
+    // auctioned off from its usual cost.
+    fn house_bid(&self, _view: &View, _road: Road, cost: i64, _auction: Auction) -> i64 {
+        cost
+    }
+
+    // Which of these squares to put the next house (or hotel) on, if any.
+    fn build(&self, view: &View, buildable: &[(Road, i64)]) -> Option<Road>;
+
+    // Pay (or use a card) to leave jail now, rather than rolling for doubles?
+    fn leave_jail(&self, _view: &View) -> bool {
+        self.jail() == JailStrategy::ShortStay
+    }
+
+    // Which of these squares to mortgage to find some cash we're short of, if any.
+    fn mortgage(
+        &self,
+        _view: &View,
+        _shortfall: i64,
+        mortgageable: &[(Road, i64)],
+    ) -> Option<Road> {
+        cheapest(mortgageable)
+    }
+
+    // Which of our mortgaged squares to pay off, if any, at what it would cost.
+    fn unmortgage(&self, view: &View, mortgaged: &[(Road, i64)]) -> Option<Road> {
+        affordable(view, mortgaged)
+    }
+}
+
+// Bid up to what a square is worth to us at an English auction, where the winner only pays a
+// little over the runner up, but shade a sealed bid, where the winner pays what they wrote.
+fn offer(worth: i64, auction: Auction) -> i64 {
+    match auction {
+        Auction::Sealed => worth * 3 / 4,
+        _ => worth,
+    }
+}
+
+fn list(road: Road) -> i64 {
+    road.price().unwrap() as i64
+}
+
+// The first choice we can pay for out of cash in hand.
+fn affordable(view: &View, choices: &[(Road, i64)]) -> Option<Road> {
+    choices
+        .iter()
+        .find(|(_, cost)| *cost <= view.cash)
+        .map(|(r, _)| *r)
+}
+
+fn cheapest(mortgageable: &[(Road, i64)]) -> Option<Road> {
+    mortgageable.iter().min_by_key(|(_, v)| *v).map(|(r, _)| *r)
+}
+
+// Buy everything, and build wherever we can, mortgaging the cheapest squares to pay for it.
+pub struct AlwaysBuy {
+    pub jail: JailStrategy,
+}
+
+impl Strategy for AlwaysBuy {
+    fn name(&self) -> String {
+        String::from("always")
+    }
+
+    fn jail(&self) -> JailStrategy {
+        self.jail
+    }
+
+    fn buy(&self, _view: &View, _road: Road) -> bool {
+        true
+    }
+
+    fn bid(&self, _view: &View, road: Road, auction: Auction) -> i64 {
+        offer(list(road), auction)
+    }
+
+    fn build(&self, _view: &View, buildable: &[(Road, i64)]) -> Option<Road> {
+        buildable.first().map(|(r, _)| *r)
+    }
+}
+
+// As AlwaysBuy, but leave the stations and utilities alone.
+pub struct NoStationsOrUtilities {
+    pub jail: JailStrategy,
+}
+
+impl Strategy for NoStationsOrUtilities {
+    fn name(&self) -> String {
+        String::from("no-utilities")
+    }
+
+    fn jail(&self) -> JailStrategy {
+        self.jail
+    }
+
+    fn buy(&self, _view: &View, road: Road) -> bool {
+        !matches!(
+            BoardGroup::classify_road(road),
+            BoardGroup::Station | BoardGroup::Utility
+        )
+    }
+
+    fn bid(&self, view: &View, road: Road, auction: Auction) -> i64 {
+        if self.buy(view, road) {
+            offer(list(road), auction)
+        } else {
+            0
+        }
+    }
+
+    fn build(&self, view: &View, buildable: &[(Road, i64)]) -> Option<Road> {
+        affordable(view, buildable)
+    }
+}
+
+// Only buy the orange and red squares, which see the most traffic out of jail.
+pub struct OrangesAndReds {
+    pub jail: JailStrategy,
+}
+
+impl Strategy for OrangesAndReds {
+    fn name(&self) -> String {
+        String::from("oranges-reds")
+    }
+
+    fn jail(&self) -> JailStrategy {
+        self.jail
+    }
+
+    fn buy(&self, _view: &View, road: Road) -> bool {
+        matches!(
+            BoardGroup::classify_road(road),
+            BoardGroup::Orange | BoardGroup::Red
+        )
+    }
+
+    // go half as much again over the list price for the squares we're after
+    fn bid(&self, view: &View, road: Road, auction: Auction) -> i64 {
+        if self.buy(view, road) {
+            offer(list(road) * 3 / 2, auction)
+        } else {
+            0
+        }
+    }
+
+    fn build(&self, view: &View, buildable: &[(Road, i64)]) -> Option<Road> {
+        affordable(view, buildable)
+    }
+
+    fn mortgage(&self, view: &View, _shortfall: i64, mortgageable: &[(Road, i64)]) -> Option<Road> {
+        // keep hold of the sets we're collecting for as long as we can
+        let (keep, spare): (Vec<_>, Vec<_>) =
+            mortgageable.iter().partition(|(r, _)| self.buy(view, *r));
+        cheapest(&spare).or_else(|| cheapest(&keep))
+    }
+}
+
+// Buy and build freely, but never let cash fall below a reserve kept back for paying rent.
+pub struct CashReserve {
+    pub jail: JailStrategy,
+    pub reserve: i64,
+}
+
+impl Strategy for CashReserve {
+    fn name(&self) -> String {
+        format!("reserve:{}", self.reserve)
+    }
+
+    fn jail(&self) -> JailStrategy {
+        self.jail
+    }
+
+    fn buy(&self, view: &View, road: Road) -> bool {
+        view.cash - list(road) >= self.reserve
+    }
+
+    fn bid(&self, view: &View, road: Road, auction: Auction) -> i64 {
+        offer(list(road).min(view.cash - self.reserve), auction)
+    }
+
+    fn house_bid(&self, view: &View, _road: Road, cost: i64, _auction: Auction) -> i64 {
+        cost.min(view.cash - self.reserve)
+    }
+
+    fn build(&self, view: &View, buildable: &[(Road, i64)]) -> Option<Road> {
+        buildable
+            .iter()
+            .find(|(_, cost)| view.cash - cost >= self.reserve)
+            .map(|(r, _)| *r)
+    }
+
+    fn unmortgage(&self, view: &View, mortgaged: &[(Road, i64)]) -> Option<Road> {
+        mortgaged
+            .iter()
+            .find(|(_, cost)| view.cash - cost >= self.reserve)
+            .map(|(r, _)| *r)
+    }
+
+    // only pay our way out if we can do so and still keep the reserve
+    fn leave_jail(&self, view: &View) -> bool {
+        self.jail == JailStrategy::ShortStay && view.cash - 50 >= self.reserve
+    }
+
+    // the smallest mortgage that covers what we're short, or failing that the largest
+    fn mortgage(&self, _view: &View, shortfall: i64, mortgageable: &[(Road, i64)]) -> Option<Road> {
+        mortgageable
+            .iter()
+            .filter(|(_, v)| *v >= shortfall)
+            .min_by_key(|(_, v)| *v)
+            .or_else(|| mortgageable.iter().max_by_key(|(_, v)| *v))
+            .map(|(r, _)| *r)
+    }
+}
+
+// Buy everything, but stop at four houses rather than trading them in for hotels, and outbid
+// everyone for the last few, so that the bank runs out and nobody else can build.
+pub struct HouseHoarder {
+    pub jail: JailStrategy,
+}
+
+impl Strategy for HouseHoarder {
+    fn name(&self) -> String {
+        String::from("hoard")
+    }
+
+    fn jail(&self) -> JailStrategy {
+        self.jail
+    }
+
+    fn buy(&self, _view: &View, _road: Road) -> bool {
+        true
+    }
+
+    fn bid(&self, _view: &View, road: Road, auction: Auction) -> i64 {
+        offer(list(road), auction)
+    }
+
+    fn house_bid(&self, _view: &View, _road: Road, cost: i64, _auction: Auction) -> i64 {
+        3 * cost
+    }
+
+    fn build(&self, view: &View, buildable: &[(Road, i64)]) -> Option<Road> {
+        buildable
+            .iter()
+            .find(|(r, cost)| view.houses[*r as usize] < 4 && *cost <= view.cash)
+            .map(|(r, _)| *r)
+    }
+}
+
+// Make a strategy from its name on the command line.
+pub fn named(name: &str, jail: JailStrategy) -> Result<Box<dyn Strategy + Send>, String> {
+    match name {
+        "always" => Ok(Box::new(AlwaysBuy { jail })),
+        "no-utilities" => Ok(Box::new(NoStationsOrUtilities { jail })),
+        "oranges-reds" => Ok(Box::new(OrangesAndReds { jail })),
+        "hoard" => Ok(Box::new(HouseHoarder { jail })),
+        _ => match name.strip_prefix("reserve:") {
+            Some(reserve) => match reserve.parse() {
+                Ok(reserve) => Ok(Box::new(CashReserve { jail, reserve })),
+                Err(_) => Err(format!("reserve expects a number, got '{}'", reserve)),
+            },
+            None => Err(format!("unknown strategy '{}'", name)),
+        },
+    }
+}